@@ -1,24 +1,24 @@
 use crate::packet::Packet;
-use system_rust::{ports, Read, Write};
+use sand::{ports, Read, Write};
 
-pub(crate) struct Ports {
-    pub(crate) pro_to_ic: ports::In<Packet>,
-    pub(crate) ic_to_pro: ports::Out<Packet>,
+ports! {
+    pro_to_ic <- Packet,
+    ic_to_pro -> Packet,
 
-    pub(crate) ic_to_copro1_ready: ports::Out<bool>,
-    pub(crate) ic_to_copro1: ports::Out<Packet>,
-    pub(crate) copro1_to_ic_ready: ports::In<bool>,
-    pub(crate) copro1_to_ic: ports::In<Packet>,
+    ic_to_copro1_ready -> bool,
+    ic_to_copro1 -> Packet,
+    copro1_to_ic_ready <- bool,
+    copro1_to_ic <- Packet,
 
-    pub(crate) ic_to_copro2_ready: ports::Out<bool>,
-    pub(crate) ic_to_copro2: ports::Out<Packet>,
-    pub(crate) copro2_to_ic_ready: ports::In<bool>,
-    pub(crate) copro2_to_ic: ports::In<Packet>,
+    ic_to_copro2_ready -> bool,
+    ic_to_copro2 -> Packet,
+    copro2_to_ic_ready <- bool,
+    copro2_to_ic <- Packet,
 
-    pub(crate) ic_to_copro3_ready: ports::Out<bool>,
-    pub(crate) ic_to_copro3: ports::Out<Packet>,
-    pub(crate) copro3_to_ic_ready: ports::In<bool>,
-    pub(crate) copro3_to_ic: ports::In<Packet>,
+    ic_to_copro3_ready -> bool,
+    ic_to_copro3 -> Packet,
+    copro3_to_ic_ready <- bool,
+    copro3_to_ic <- Packet
 }
 
 pub(crate) async fn process(ports: &mut Ports) {
@@ -61,8 +61,9 @@ pub(crate) async fn process(ports: &mut Ports) {
             }
         };
 
-        ic_to_copro.nb_write(packet);
-        ic_to_copro_ready.nb_write(true);
+        if ic_to_copro.nb_write(packet).is_err() || ic_to_copro_ready.nb_write(true).is_err() {
+            return;
+        }
 
         let response = match copro_to_ic_ready.b_read().await {
             Ok(ready) => {
@@ -82,6 +83,8 @@ pub(crate) async fn process(ports: &mut Ports) {
             }
         };
 
-        ports.ic_to_pro.nb_write(response);
+        if ports.ic_to_pro.nb_write(response).is_err() {
+            return;
+        }
     }
 }
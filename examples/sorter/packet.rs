@@ -1,7 +1,12 @@
-#[derive(Clone, PartialEq)]
+use sand::Signal;
+
+/// Size of the payload of a packet, in words.
+pub(crate) const PAYLOAD_SIZE: usize = 10;
+
+#[derive(Clone, PartialEq, Signal)]
 pub struct Packet {
     pub(crate) id: u32,
     pub(crate) address: u32,
-    pub(crate) payload: Vec<u32>,
+    pub(crate) payload: [u32; PAYLOAD_SIZE],
     pub(crate) payload_size: u32,
 }
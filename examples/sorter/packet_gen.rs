@@ -1,27 +1,31 @@
-use crate::packet::Packet;
+use crate::packet::{Packet, PAYLOAD_SIZE};
 use rand::Rng;
-use system_rust::{ports, Read, Write};
+use sand::{ports, Read, Write};
 
-pub(crate) struct Ports {
-    pub(crate) pro_to_ic: ports::Out<Packet>,
-    pub(crate) ic_to_pro: ports::In<Packet>,
+ports! {
+    pro_to_ic -> Packet,
+    ic_to_pro <- Packet
 }
 
 pub(crate) async fn process(ports: &mut Ports) {
     for address in 0..4 {
-        let payload: Vec<u32>;
+        let mut payload = [0; PAYLOAD_SIZE];
         {
             let mut rng = rand::thread_rng();
-            payload = (0..10).map(|_| rng.gen_range(0..1000)).collect();
+            payload
+                .iter_mut()
+                .for_each(|word| *word = rng.gen_range(0..1000));
         }
-        let payload_size = payload.len() as u32;
+        let payload_size = PAYLOAD_SIZE as u32;
         let packet = Packet {
             id: address,
             address,
             payload,
             payload_size,
         };
-        ports.pro_to_ic.nb_write(packet.clone());
+        if ports.pro_to_ic.nb_write(packet.clone()).is_err() {
+            return;
+        }
 
         match ports.ic_to_pro.b_read().await {
             Ok(response) => {
@@ -0,0 +1,132 @@
+//! Command-line tool running the testbenches of a cargo package.
+//!
+//! A testbench is an example or a binary target whose `main` calls `sand::cli::main`. This tool
+//! builds the target with cargo and forwards the command to it, e.g.
+//! `sand --example sorter run --until 1us`.
+
+use clap::{App, AppSettings, Arg, ArgGroup};
+use std::ffi::OsString;
+use std::process::{exit, Command};
+
+/// Options of this tool that take a value.
+const VALUED_OPTIONS: [&str; 3] = ["--example", "--bin", "--manifest-path"];
+
+fn main() {
+    let args: Vec<OsString> = std::env::args_os().collect();
+    let matches = App::new("sand")
+        .about("Run the testbenches of a cargo package")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .setting(AppSettings::VersionlessSubcommands)
+        .arg(
+            Arg::with_name("example")
+                .long("example")
+                .value_name("NAME")
+                .help("Testbench example target"),
+        )
+        .arg(
+            Arg::with_name("bin")
+                .long("bin")
+                .value_name("NAME")
+                .help("Testbench binary target"),
+        )
+        .group(
+            ArgGroup::with_name("target")
+                .args(&["example", "bin"])
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("manifest-path")
+                .long("manifest-path")
+                .value_name("PATH")
+                .help("Path to the Cargo.toml of the package"),
+        )
+        .arg(
+            Arg::with_name("release")
+                .long("release")
+                .help("Build the testbench with optimizations"),
+        )
+        .subcommands(sand::cli::commands())
+        .get_matches_from(&args);
+
+    let mut cargo = Command::new(std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+    cargo.args(["run", "--quiet"]);
+    if let Some(path) = matches.value_of_os("manifest-path") {
+        cargo.arg("--manifest-path").arg(path);
+    }
+    if matches.is_present("release") {
+        cargo.arg("--release");
+    }
+    match (matches.value_of_os("example"), matches.value_of_os("bin")) {
+        (Some(example), _) => cargo.arg("--example").arg(example),
+        (_, Some(bin)) => cargo.arg("--bin").arg(bin),
+        _ => unreachable!("The target group is required."),
+    };
+    cargo.arg("--").args(&args[command_index(&args)..]);
+
+    match cargo.status() {
+        Ok(status) => exit(status.code().unwrap_or(1)),
+        Err(err) => {
+            eprintln!("error: unable to run cargo: {}", err);
+            exit(1);
+        }
+    }
+}
+
+/// Returns the index of the command, which is the first argument that is not one of our options.
+fn command_index(args: &[OsString]) -> usize {
+    let mut index = 1;
+    while let Some(arg) = args.get(index).and_then(|arg| arg.to_str()) {
+        if VALUED_OPTIONS.contains(&arg) {
+            index += 2;
+        } else if arg.starts_with("--") {
+            index += 1;
+        } else {
+            break;
+        }
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn test_command_index() {
+        assert_eq!(3, command_index(&args(&["sand", "--example", "x", "run"])));
+        assert_eq!(
+            6,
+            command_index(&args(&[
+                "sand",
+                "--release",
+                "--manifest-path",
+                "sorter/Cargo.toml",
+                "--bin",
+                "x",
+                "report",
+                "--until",
+                "1us",
+            ]))
+        );
+        // The options of the command are forwarded with it.
+        let with_command = args(&["sand", "--bin", "x", "run", "--vcd", "out.vcd"]);
+        assert_eq!(
+            &with_command[3..],
+            &with_command[command_index(&with_command)..]
+        );
+    }
+
+    #[test]
+    fn test_command_index_without_command() {
+        assert_eq!(3, command_index(&args(&["sand", "--example", "x"])));
+        assert_eq!(
+            4,
+            command_index(&args(&["sand", "--release", "--example", "x"]))
+        );
+        assert_eq!(1, command_index(&args(&["sand"])));
+    }
+}
@@ -0,0 +1,349 @@
+//! This module holds the command-line interface of the testbenches.
+//!
+//! A testbench hands the elaboration of its design to [`main`], which parses the command line
+//! and runs one of the [`commands`]:
+//! ```no_run
+//! sand::cli::main(|| {
+//!     let mut sim = sand::Simulator::new();
+//!     sim.spawn(async {});
+//!     sim
+//! });
+//! ```
+//! The `sand` binary forwards the same commands to the testbench targets of a cargo package.
+
+use crate::kernel::elaboration_failed;
+use crate::{synth, LagMode, SimTime, Simulator};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use std::fs::File;
+use std::io;
+use std::time::Instant;
+
+/// Returns the subcommands understood by [`main`].
+pub fn commands<'a, 'b>() -> Vec<App<'a, 'b>> {
+    let until = Arg::with_name("until")
+        .long("until")
+        .value_name("TIME")
+        .help("Stop the simulation at this simulated time, as in 100ns")
+        .validator(|time| {
+            time.parse::<SimTime>()
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        });
+    let vcd = Arg::with_name("vcd")
+        .long("vcd")
+        .value_name("FILE")
+        .help("Dump the waveforms of the traced signals to a Value Change Dump file");
+    let lagged = Arg::with_name("lagged")
+        .long("lagged")
+        .value_name("MODE")
+        .possible_values(&["count", "fatal"])
+        .help("Count the values missed by the readers of each signal, or stop on the first one");
+    vec![
+        SubCommand::with_name("elaborate").about("Elaborate the design without simulating it"),
+        SubCommand::with_name("hierarchy").about("Print the tree of the elaborated design"),
+        SubCommand::with_name("run")
+            .about("Simulate the design (default)")
+            .arg(until.clone())
+            .arg(vcd.clone())
+            .arg(lagged.clone()),
+        SubCommand::with_name("report")
+            .about("Simulate the design and print a report")
+            .arg(
+                Arg::with_name("kind")
+                    .possible_values(&["cost"])
+                    .default_value("cost")
+                    .help("What to report"),
+            )
+            .arg(until)
+            .arg(vcd)
+            .arg(lagged),
+        SubCommand::with_name("llhd")
+            .about("Write the LLHD IR of the entities registered for synthesis")
+            .arg(
+                Arg::with_name("output")
+                    .short("o")
+                    .long("output")
+                    .value_name("FILE")
+                    .help("Write to this .llhd file instead of stdout"),
+            ),
+    ]
+}
+
+/// Parse the command line and run the command on the design built by `elaborate`.
+///
+/// Without a command, the design is simulated until no process can be woken up anymore. The
+/// process exits on an invalid command line or an I/O error.
+pub fn main<F>(elaborate: F)
+where
+    F: FnOnce() -> Simulator,
+{
+    let matches = App::new("sand")
+        .about("Simulate a sand design")
+        .setting(AppSettings::VersionlessSubcommands)
+        .subcommands(commands())
+        .get_matches();
+    let runtime = tokio::runtime::Runtime::new().expect("Unable to start the tokio runtime.");
+    // The processes may print too, so stdout must not stay locked during the simulation.
+    if let Err(err) = runtime.block_on(execute(&matches, elaborate, &mut io::stdout())) {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+/// Run the command in `matches` on the design built by `elaborate`, and write its output to `out`.
+pub async fn execute<F, W>(matches: &ArgMatches<'_>, elaborate: F, out: &mut W) -> io::Result<()>
+where
+    F: FnOnce() -> Simulator,
+    W: io::Write,
+{
+    let until = |args: Option<&ArgMatches>| {
+        args.and_then(|args| args.value_of("until")).map(|time| {
+            time.parse::<SimTime>()
+                .expect("The time is validated by clap.")
+        })
+    };
+    let elaborate = |args: Option<&ArgMatches>| -> io::Result<Simulator> {
+        let mut sim = elaborate();
+        if let Some(path) = args.and_then(|args| args.value_of_os("vcd")) {
+            sim.dump_vcd(io::BufWriter::new(File::create(path)?));
+        }
+        match args.and_then(|args| args.value_of("lagged")) {
+            Some("count") => sim.set_lag_mode(LagMode::Count),
+            Some("fatal") => sim.set_lag_mode(LagMode::Fatal),
+            _ => {}
+        }
+        Ok(sim)
+    };
+    match matches.subcommand() {
+        ("elaborate", args) => {
+            let sim = elaborate(args)?;
+            check(&sim)?;
+            writeln!(out, "Elaborated {} processes", sim.statistics().processes)
+        }
+        ("llhd", args) => {
+            let sim = elaborate(None)?;
+            let module = sim
+                .llhd_module()
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            match args.and_then(|args| args.value_of_os("output")) {
+                Some(path) => synth::write(io::BufWriter::new(File::create(path)?), &module),
+                None => synth::write(out, &module),
+            }
+            Ok(())
+        }
+        ("hierarchy", _) => {
+            let sim = elaborate(None)?;
+            write!(out, "{}", sim.hierarchy())
+        }
+        ("report", args) => {
+            let mut sim = elaborate(args)?;
+            check(&sim)?;
+            let start = Instant::now();
+            let end = simulate(&mut sim, until(args)).await;
+            let statistics = sim.statistics();
+            writeln!(out, "Simulated time: {}", end)?;
+            writeln!(out, "Processes: {}", statistics.processes)?;
+            writeln!(out, "Time steps: {}", statistics.time_steps)?;
+            writeln!(out, "Delta cycles: {}", statistics.delta_cycles)?;
+            writeln!(out, "Lagged values: {}", statistics.lagged_values)?;
+            writeln!(out, "Wall time: {:?}", start.elapsed())?;
+            report_lagged(&sim, out)
+        }
+        (_, args) => {
+            let mut sim = elaborate(args)?;
+            check(&sim)?;
+            let end = simulate(&mut sim, until(args)).await;
+            writeln!(out, "Simulation ended at {}", end)?;
+            report_lagged(&sim, out)
+        }
+    }
+}
+
+/// Check the connections of the design before simulating it.
+fn check(sim: &Simulator) -> io::Result<()> {
+    sim.elaborate()
+        .map_err(|errors| io::Error::new(io::ErrorKind::InvalidData, elaboration_failed(&errors)))
+}
+
+/// Write a warning for every signal whose readers missed values.
+fn report_lagged(sim: &Simulator, out: &mut impl io::Write) -> io::Result<()> {
+    sim.lagged()
+        .iter()
+        .try_for_each(|lagged| writeln!(out, "warning: {}", lagged))
+}
+
+async fn simulate(sim: &mut Simulator, until: Option<SimTime>) -> SimTime {
+    match until {
+        Some(limit) => sim.run_until(limit).await,
+        None => sim.run().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hierarchy::{Direction, Kind};
+    use crate::{wait_for, Read, Write};
+    use std::time::Duration;
+
+    fn clock() -> Simulator {
+        let mut sim = Simulator::new();
+        sim.connect("top.gen.clk", "top.clk", Direction::Out);
+        sim.connect("top.cpu.clk", "top.clk", Direction::In);
+        sim.synthesize(synth::Entity {
+            name: "tick",
+            assembly: "entity @tick () -> (i1$ %out) {\n}\n",
+        });
+        sim.spawn(async {
+            loop {
+                wait_for(Duration::from_nanos(10)).await;
+            }
+        });
+        sim
+    }
+
+    /// A reader missing the first two of the three values of a signal.
+    fn lagging() -> Simulator {
+        let mut sim = Simulator::new();
+        let (tx, mut rx) = crate::signal::named::<u32>("top.count");
+        sim.spawn(async move {
+            for i in 1..=3 {
+                tx.nb_write(i).unwrap();
+                crate::wait().await.unwrap();
+            }
+            wait_for(Duration::from_nanos(5)).await;
+            let _ = rx.b_read().await;
+        });
+        sim
+    }
+
+    mod counter {
+        use crate::{wait_for, Write};
+        use std::time::Duration;
+
+        crate::ports! {
+            count -> u8
+        }
+
+        pub(crate) async fn process(ports: &mut Ports) {
+            for i in 1..=2 {
+                ports.count.nb_write(i).unwrap();
+                wait_for(Duration::from_nanos(10)).await;
+            }
+        }
+    }
+
+    mod monitor {
+        use crate::Read;
+
+        crate::ports! {
+            count <- u8
+        }
+
+        pub(crate) async fn process(ports: &mut Ports) {
+            while ports.count.b_read().await.is_ok() {}
+        }
+    }
+
+    /// Modules instantiated with `connections!`.
+    fn connected() -> Simulator {
+        crate::connections! {
+            #![trace]
+            counter.count -> count;
+            monitor.count <- count;
+        }
+    }
+
+    async fn output(args: &[&str]) -> String {
+        output_of(args, clock).await
+    }
+
+    async fn output_of(args: &[&str], elaborate: fn() -> Simulator) -> String {
+        let matches = App::new("sand")
+            .subcommands(commands())
+            .get_matches_from(args);
+        let mut out = Vec::new();
+        execute(&matches, elaborate, &mut out).await.unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_run_until() {
+        assert_eq!(
+            "Simulation ended at 25 ns\n",
+            output(&["sand", "run", "--until", "25ns"]).await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_report() {
+        let report = output(&["sand", "report", "--until", "1us"]).await;
+        assert!(report.starts_with("Simulated time: 1000 ns\nProcesses: 1\nTime steps: 100\n"));
+    }
+
+    #[tokio::test]
+    async fn test_lagged() {
+        assert_eq!(
+            "Simulation ended at 5 ns\nwarning: A reader of the signal `top.count` missed 2 values.\n",
+            output_of(&["sand", "run", "--lagged", "count"], lagging).await
+        );
+        assert_eq!(
+            "Simulation ended at 5 ns\n",
+            output_of(&["sand", "run"], lagging).await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_vcd() {
+        let path = std::env::temp_dir().join(format!("sand-test-{}.vcd", std::process::id()));
+        output_of(&["sand", "run", "--vcd", path.to_str().unwrap()], connected).await;
+        let vcd = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        // The signals traced by `connections!` are under the `top` module.
+        assert!(vcd.contains("$scope module top $end\n$var wire 8 ! count [7:0] $end\n"));
+        assert!(vcd.contains("#10\nb00000010 !\n"));
+    }
+
+    #[tokio::test]
+    async fn test_elaboration_errors() {
+        let matches = App::new("sand")
+            .subcommands(commands())
+            .get_matches_from(["sand", "elaborate"]);
+        let unconnected = || {
+            let mut sim = clock();
+            sim.register("top.irq", Kind::Signal);
+            sim
+        };
+        let err = execute(&matches, unconnected, &mut Vec::new())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            "The elaboration failed:\nThe signal `top.irq` is not connected to a port.",
+            err.to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hierarchy() {
+        assert_eq!(
+            "top (module)\n  gen (module)\n    clk (port)\n  clk (signal)\n  cpu (module)\n    clk (port)\n",
+            output(&["sand", "hierarchy"]).await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_llhd() {
+        assert_eq!(
+            "entity @tick () -> (i1$ %out) {\n}\n",
+            output(&["sand", "llhd"]).await
+        );
+    }
+
+    #[test]
+    fn test_invalid_time() {
+        let matches = App::new("sand")
+            .subcommands(commands())
+            .get_matches_from_safe(["sand", "run", "--until", "soon"]);
+        assert!(matches.is_err());
+    }
+}
@@ -0,0 +1,405 @@
+//! Various simulation errors.
+//!
+//! Every operation returns its own error type, listing only the errors it can return, and each of
+//! them converts into the [`Error`] of the whole crate, so that they can be propagated together
+//! with `?`.
+
+use crate::SimTime;
+use std::fmt;
+
+/// Non blocking read errors:
+/// - [`NBReadError::Empty`]
+/// - [`NBReadError::Closed`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NBReadError {
+    /// When the signal has never been written to, it is in an undefined state.
+    Empty,
+    /// When all the signal [Sender](crate::signal::Sender)s are dropped, the signal becomes
+    /// closed. This is useful to end the simulation, without calling [`stop`](crate::stop).
+    Closed,
+}
+
+impl fmt::Display for NBReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            NBReadError::Empty => "The signal has never been written to.",
+            NBReadError::Closed => "The signal is closed.",
+        })
+    }
+}
+
+impl std::error::Error for NBReadError {}
+
+/// Blocking read errors:
+/// - [`BReadError::Closed`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BReadError {
+    /// When all the signal [Sender](crate::signal::Sender)s are dropped, the signal becomes
+    /// closed. This is useful to end the simulation, without calling [`stop`](crate::stop).
+    Closed,
+}
+
+impl fmt::Display for BReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BReadError::Closed => f.write_str("The signal is closed."),
+        }
+    }
+}
+
+impl std::error::Error for BReadError {}
+
+/// Non blocking write errors:
+/// - [`NBWriteError::Full`]
+/// - [`NBWriteError::Closed`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NBWriteError {
+    /// When the channel has no room left for the value in the current *delta-cycle*.
+    Full,
+    /// When the channel receiver is dropped, nothing can read the value anymore.
+    Closed,
+}
+
+impl fmt::Display for NBWriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            NBWriteError::Full => "The channel is full.",
+            NBWriteError::Closed => "The channel has no receiver left.",
+        })
+    }
+}
+
+impl std::error::Error for NBWriteError {}
+
+/// Blocking write errors:
+/// - [`BWriteError::Closed`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BWriteError {
+    /// When the channel receiver is dropped, nothing can read the value anymore.
+    Closed,
+}
+
+impl fmt::Display for BWriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BWriteError::Closed => f.write_str("The channel has no receiver left."),
+        }
+    }
+}
+
+impl std::error::Error for BWriteError {}
+
+/// A reader of a signal was too slow and missed some of its values.
+///
+/// The kernel counts or raises them depending on its [`LagMode`](crate::LagMode).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lagged {
+    /// Hierarchical path of the signal, if it has one.
+    pub signal: Option<String>,
+    /// Number of values the reader missed.
+    pub skipped: u64,
+}
+
+impl fmt::Display for Lagged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.signal {
+            Some(signal) => write!(
+                f,
+                "A reader of the signal `{}` missed {} values.",
+                signal, self.skipped
+            ),
+            None => write!(f, "A reader of a signal missed {} values.", self.skipped),
+        }
+    }
+}
+
+impl std::error::Error for Lagged {}
+
+/// Two drivers of a resolved signal drive conflicting strong values.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShortCircuit {
+    /// Names of the conflicting drivers.
+    pub drivers: (String, String),
+    /// Simulated time of the conflict.
+    pub time: SimTime,
+}
+
+impl fmt::Display for ShortCircuit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Short circuit between drivers `{}` and `{}` at {}.",
+            self.drivers.0, self.drivers.1, self.time
+        )
+    }
+}
+
+impl std::error::Error for ShortCircuit {}
+
+/// Processes are blocked reading signals and no timed event is pending, so the simulation cannot
+/// progress anymore.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Deadlock {
+    /// The blocked processes.
+    pub blocked: Vec<Blocked>,
+    /// Simulated time of the deadlock.
+    pub time: SimTime,
+}
+
+/// A process blocked in a [`Deadlock`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Blocked {
+    /// Hierarchical path of the process, if it has one.
+    pub process: Option<String>,
+    /// Hierarchical path of the signal the process waits on, if it is a named signal.
+    pub signal: Option<String>,
+}
+
+impl fmt::Display for Deadlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Deadlock at {}, the blocked processes are:", self.time)?;
+        for blocked in self.blocked.iter() {
+            match &blocked.process {
+                Some(process) => write!(f, "\n- `{}`", process)?,
+                None => write!(f, "\n- an unnamed process")?,
+            }
+            match &blocked.signal {
+                Some(signal) => write!(f, " waits on the signal `{}`", signal)?,
+                None => write!(f, " waits on an unnamed signal")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Deadlock {}
+
+/// Errors in the structure of a design, found before it is simulated.
+///
+/// The ports and signals are named by their hierarchical path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ElaborationError {
+    /// A port is not connected to any signal.
+    UnconnectedPort(String),
+    /// A signal is not connected to any port.
+    UnconnectedSignal(String),
+    /// An input port reads a signal that no port drives.
+    DanglingInput {
+        /// The input port.
+        port: String,
+        /// The signal without a driver.
+        signal: String,
+    },
+    /// An output port drives a signal that no port reads.
+    UnreadOutput {
+        /// The output port.
+        port: String,
+        /// The signal without a reader.
+        signal: String,
+    },
+    /// A signal that is not resolved is driven by several output ports.
+    MultipleDrivers {
+        /// The signal.
+        signal: String,
+        /// The ports driving the signal.
+        drivers: Vec<String>,
+    },
+}
+
+impl fmt::Display for ElaborationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ElaborationError::UnconnectedPort(port) => {
+                write!(f, "The port `{}` is not connected to a signal.", port)
+            }
+            ElaborationError::UnconnectedSignal(signal) => {
+                write!(f, "The signal `{}` is not connected to a port.", signal)
+            }
+            ElaborationError::DanglingInput { port, signal } => write!(
+                f,
+                "The input port `{}` reads the signal `{}`, which has no driver.",
+                port, signal
+            ),
+            ElaborationError::UnreadOutput { port, signal } => write!(
+                f,
+                "The output port `{}` drives the signal `{}`, which has no reader.",
+                port, signal
+            ),
+            ElaborationError::MultipleDrivers { signal, drivers } => write!(
+                f,
+                "The signal `{}` is driven by several ports: `{}`, use a resolved signal instead.",
+                signal,
+                drivers.join("`, `")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ElaborationError {}
+
+/// Any error of the crate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// A signal was read before it was ever written to.
+    Empty,
+    /// A signal or channel is closed, so nothing can be written to or read from it anymore.
+    Closed,
+    /// A channel is full.
+    Full,
+    /// A reader of a signal missed some values.
+    Lagged(Lagged),
+    /// Two drivers of a resolved signal are in conflict.
+    ShortCircuit(ShortCircuit),
+    /// The design is not well formed.
+    Elaboration(ElaborationError),
+    /// The simulation cannot progress anymore.
+    Deadlock(Deadlock),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Empty => NBReadError::Empty.fmt(f),
+            Error::Closed => f.write_str("The signal or channel is closed."),
+            Error::Full => NBWriteError::Full.fmt(f),
+            Error::Lagged(err) => err.fmt(f),
+            Error::ShortCircuit(err) => err.fmt(f),
+            Error::Elaboration(err) => err.fmt(f),
+            Error::Deadlock(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Lagged(err) => Some(err),
+            Error::ShortCircuit(err) => Some(err),
+            Error::Elaboration(err) => Some(err),
+            Error::Deadlock(err) => Some(err),
+            Error::Empty | Error::Closed | Error::Full => None,
+        }
+    }
+}
+
+impl From<NBReadError> for Error {
+    fn from(err: NBReadError) -> Self {
+        match err {
+            NBReadError::Empty => Error::Empty,
+            NBReadError::Closed => Error::Closed,
+        }
+    }
+}
+
+impl From<BReadError> for Error {
+    fn from(err: BReadError) -> Self {
+        match err {
+            BReadError::Closed => Error::Closed,
+        }
+    }
+}
+
+impl From<NBWriteError> for Error {
+    fn from(err: NBWriteError) -> Self {
+        match err {
+            NBWriteError::Full => Error::Full,
+            NBWriteError::Closed => Error::Closed,
+        }
+    }
+}
+
+impl From<BWriteError> for Error {
+    fn from(err: BWriteError) -> Self {
+        match err {
+            BWriteError::Closed => Error::Closed,
+        }
+    }
+}
+
+impl From<Lagged> for Error {
+    fn from(err: Lagged) -> Self {
+        Error::Lagged(err)
+    }
+}
+
+impl From<ShortCircuit> for Error {
+    fn from(err: ShortCircuit) -> Self {
+        Error::ShortCircuit(err)
+    }
+}
+
+impl From<ElaborationError> for Error {
+    fn from(err: ElaborationError) -> Self {
+        Error::Elaboration(err)
+    }
+}
+
+impl From<Deadlock> for Error {
+    fn from(err: Deadlock) -> Self {
+        Error::Deadlock(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signal::signal;
+    use crate::{Read, TimeExt, Write};
+
+    fn forward(rx: &mut impl Read<u8>, tx: &impl Write<u8>) -> Result<(), Error> {
+        tx.nb_write(rx.nb_read()?)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_conversion() {
+        let (tx, mut rx) = signal::<u8>();
+        let (out, out_rx) = signal::<u8>();
+        assert_eq!(Err(Error::Empty), forward(&mut rx, &out));
+        tx.nb_write(1).unwrap();
+        drop(out_rx);
+        let err = forward(&mut rx, &out).unwrap_err();
+        assert_eq!(Error::Closed, err);
+        assert_eq!("The signal or channel is closed.", err.to_string());
+    }
+
+    #[test]
+    fn test_simulation_error_conversion() {
+        let lagged = Lagged {
+            signal: Some("top.count".to_string()),
+            skipped: 2,
+        };
+        let short_circuit = ShortCircuit {
+            drivers: ("top.a.sda".to_string(), "top.b.sda".to_string()),
+            time: SimTime::ZERO + 10.ns(),
+        };
+        let elaboration = ElaborationError::UnconnectedPort("top.cpu.irq".to_string());
+        let deadlock = Deadlock {
+            blocked: vec![Blocked {
+                process: Some("top.cpu.process".to_string()),
+                signal: Some("top.ready".to_string()),
+            }],
+            time: SimTime::ZERO + 20.ns(),
+        };
+        let errors = [
+            (Error::from(lagged.clone()), Error::Lagged(lagged)),
+            (
+                Error::from(short_circuit.clone()),
+                Error::ShortCircuit(short_circuit),
+            ),
+            (
+                Error::from(elaboration.clone()),
+                Error::Elaboration(elaboration),
+            ),
+            (Error::from(deadlock.clone()), Error::Deadlock(deadlock)),
+        ];
+        for (err, expected) in errors {
+            assert_eq!(expected, err);
+            // The error is displayed as the error it wraps, which is its source.
+            let source = std::error::Error::source(&err).unwrap();
+            assert_eq!(source.to_string(), err.to_string());
+        }
+    }
+}
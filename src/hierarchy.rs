@@ -0,0 +1,364 @@
+//! This module holds the design hierarchy, in which every module instance, process, port and
+//! signal has a hierarchical path such as `"top.interconnect.ic_to_copro1"`.
+//!
+//! The objects are registered in the [`Hierarchy`] of the [`Simulator`](crate::Simulator) during
+//! the elaboration, with [`Simulator::register`](crate::Simulator::register) and
+//! [`Simulator::spawn_named`](crate::Simulator::spawn_named). The `connections!` macro registers
+//! the objects it instantiates under the `top` module. Every dot of a path separates a scope, and
+//! the scopes that were not registered are created as modules.
+//!
+//! The hierarchy also holds the netlist of the design, which is the [`Connection`] of every port
+//! to its signal, registered with [`Simulator::connect`](crate::Simulator::connect). The netlist
+//! is checked by [`Hierarchy::check`] before the simulation starts.
+
+use crate::error::ElaborationError;
+use std::fmt;
+
+/// Kind of an object of the design hierarchy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// A module instance, which holds other objects.
+    Module,
+    /// A process of a module.
+    Process,
+    /// A port of a module.
+    Port,
+    /// A signal connecting ports.
+    Signal,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Kind::Module => "module",
+            Kind::Process => "process",
+            Kind::Port => "port",
+            Kind::Signal => "signal",
+        })
+    }
+}
+
+/// Direction of a port, seen from its module.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// The port reads its signal.
+    In,
+    /// The port drives its signal.
+    Out,
+    /// The port reads and drives its resolved signal.
+    InOut,
+}
+
+/// A port connected to a signal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Connection {
+    /// Hierarchical path of the port.
+    pub port: String,
+    /// Hierarchical path of the signal.
+    pub signal: String,
+    /// Direction of the port.
+    pub direction: Direction,
+}
+
+impl Connection {
+    fn drives(&self) -> bool {
+        self.direction != Direction::In
+    }
+
+    fn reads(&self) -> bool {
+        self.direction != Direction::Out
+    }
+}
+
+/// An object of the design hierarchy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Node {
+    name: String,
+    path: String,
+    kind: Kind,
+    children: Vec<Node>,
+}
+
+impl Node {
+    /// Returns the name of the object in its scope, which is the last segment of its path.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the hierarchical path of the object.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the kind of the object.
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// Returns the objects in the scope of this one, in registration order.
+    pub fn children(&self) -> &[Node] {
+        &self.children
+    }
+
+    fn collect<'a>(&'a self, nodes: &mut Vec<&'a Node>) {
+        nodes.push(self);
+        self.children.iter().for_each(|child| child.collect(nodes));
+    }
+
+    fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        writeln!(
+            f,
+            "{:indent$}{} ({})",
+            "",
+            self.name,
+            self.kind,
+            indent = 2 * depth
+        )?;
+        self.children
+            .iter()
+            .try_for_each(|child| child.fmt_indented(f, depth + 1))
+    }
+}
+
+/// The tree of the objects of a design.
+///
+/// It displays as an indented tree, with one object per line.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Hierarchy {
+    roots: Vec<Node>,
+    connections: Vec<Connection>,
+}
+
+impl Hierarchy {
+    /// Construct an empty hierarchy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register an object under its hierarchical path.
+    ///
+    /// The missing scopes of the path are registered as modules. Registering a path again only
+    /// changes the kind of its object.
+    pub fn insert(&mut self, path: &str, kind: Kind) {
+        let mut nodes = &mut self.roots;
+        let mut scope = String::new();
+        let mut segments = path.split('.').peekable();
+        while let Some(name) = segments.next() {
+            if !scope.is_empty() {
+                scope.push('.');
+            }
+            scope.push_str(name);
+            let last = segments.peek().is_none();
+            let index = match nodes.iter().position(|node| node.name == name) {
+                Some(index) => index,
+                None => {
+                    nodes.push(Node {
+                        name: name.to_string(),
+                        path: scope.clone(),
+                        kind: Kind::Module,
+                        children: Vec::new(),
+                    });
+                    nodes.len() - 1
+                }
+            };
+            if last {
+                nodes[index].kind = kind;
+            }
+            nodes = &mut nodes[index].children;
+        }
+    }
+
+    /// Register the connection of a port to a signal, along with the port and the signal.
+    pub fn connect(&mut self, port: &str, signal: &str, direction: Direction) {
+        self.insert(port, Kind::Port);
+        if self.find(signal).is_none() {
+            self.insert(signal, Kind::Signal);
+        }
+        self.connections.push(Connection {
+            port: port.to_string(),
+            signal: signal.to_string(),
+            direction,
+        });
+    }
+
+    /// Returns the connections of the ports, in registration order.
+    pub fn connections(&self) -> &[Connection] {
+        &self.connections
+    }
+
+    /// Check the netlist, and return its errors in the order of the hierarchy:
+    /// - the ports and signals without a connection,
+    /// - the input ports of a signal without a driver,
+    /// - the output ports of a signal without a reader,
+    /// - the signals driven by several output ports, as only resolved signals can have several
+    ///   drivers.
+    ///
+    /// The types of the ports and signals are checked by the compiler.
+    pub fn check(&self) -> Vec<ElaborationError> {
+        let mut errors = Vec::new();
+        for node in self.iter() {
+            let path = node.path();
+            match node.kind {
+                Kind::Port if !self.connections.iter().any(|c| c.port == path) => {
+                    errors.push(ElaborationError::UnconnectedPort(path.to_string()));
+                }
+                Kind::Signal => {
+                    let connections: Vec<&Connection> = self
+                        .connections
+                        .iter()
+                        .filter(|connection| connection.signal == path)
+                        .collect();
+                    let drivers: Vec<&Connection> =
+                        connections.iter().copied().filter(|c| c.drives()).collect();
+                    if connections.is_empty() {
+                        errors.push(ElaborationError::UnconnectedSignal(path.to_string()));
+                    } else if drivers.is_empty() {
+                        errors.extend(connections.iter().map(|connection| {
+                            ElaborationError::DanglingInput {
+                                port: connection.port.clone(),
+                                signal: path.to_string(),
+                            }
+                        }));
+                    } else if !connections.iter().any(|c| c.reads()) {
+                        errors.extend(connections.iter().map(|connection| {
+                            ElaborationError::UnreadOutput {
+                                port: connection.port.clone(),
+                                signal: path.to_string(),
+                            }
+                        }));
+                    }
+                    if drivers.len() > 1 && drivers.iter().any(|c| c.direction == Direction::Out) {
+                        errors.push(ElaborationError::MultipleDrivers {
+                            signal: path.to_string(),
+                            drivers: drivers.iter().map(|c| c.port.clone()).collect(),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        errors
+    }
+
+    /// Returns the object registered under the path.
+    pub fn find(&self, path: &str) -> Option<&Node> {
+        let mut nodes = &self.roots;
+        let mut found = None;
+        for name in path.split('.') {
+            let node = nodes.iter().find(|node| node.name == name)?;
+            nodes = &node.children;
+            found = Some(node);
+        }
+        found
+    }
+
+    /// Returns the objects without a scope.
+    pub fn roots(&self) -> &[Node] {
+        &self.roots
+    }
+
+    /// Returns every object of the hierarchy, each scope being followed by its content.
+    pub fn iter(&self) -> impl Iterator<Item = &Node> {
+        let mut nodes = Vec::new();
+        self.roots.iter().for_each(|root| root.collect(&mut nodes));
+        nodes.into_iter()
+    }
+
+    /// Returns true if no object is registered.
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+}
+
+impl fmt::Display for Hierarchy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.roots
+            .iter()
+            .try_for_each(|root| root.fmt_indented(f, 0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let mut hierarchy = Hierarchy::new();
+        hierarchy.connect("top.cpu.data", "top.data", Direction::Out);
+        hierarchy.connect("top.ram.data", "top.data", Direction::In);
+        hierarchy.connect("top.cpu.sda", "top.sda", Direction::InOut);
+        hierarchy.connect("top.ram.sda", "top.sda", Direction::InOut);
+        assert!(hierarchy.check().is_empty());
+
+        hierarchy.insert("top.cpu.irq", Kind::Port);
+        hierarchy.insert("top.unused", Kind::Signal);
+        hierarchy.connect("top.cpu.addr", "top.addr", Direction::Out);
+        hierarchy.connect("top.dma.addr", "top.addr", Direction::Out);
+        hierarchy.connect("top.ram.ready", "top.ready", Direction::In);
+        assert_eq!(
+            vec![
+                ElaborationError::UnconnectedPort("top.cpu.irq".to_string()),
+                ElaborationError::UnconnectedSignal("top.unused".to_string()),
+                ElaborationError::UnreadOutput {
+                    port: "top.cpu.addr".to_string(),
+                    signal: "top.addr".to_string(),
+                },
+                ElaborationError::UnreadOutput {
+                    port: "top.dma.addr".to_string(),
+                    signal: "top.addr".to_string(),
+                },
+                ElaborationError::MultipleDrivers {
+                    signal: "top.addr".to_string(),
+                    drivers: vec!["top.cpu.addr".to_string(), "top.dma.addr".to_string()],
+                },
+                ElaborationError::DanglingInput {
+                    port: "top.ram.ready".to_string(),
+                    signal: "top.ready".to_string(),
+                },
+            ],
+            hierarchy.check()
+        );
+        assert_eq!(
+            "The signal `top.addr` is driven by several ports: `top.cpu.addr`, `top.dma.addr`, use a resolved signal instead.",
+            hierarchy.check()[4].to_string()
+        );
+    }
+
+    #[test]
+    fn test_hierarchy() {
+        let mut hierarchy = Hierarchy::new();
+        hierarchy.insert("top.ic_to_pro", Kind::Signal);
+        hierarchy.insert("top.interconnect.ic_to_pro", Kind::Port);
+        hierarchy.insert("top.interconnect.process", Kind::Process);
+        hierarchy.insert("top.interconnect.process", Kind::Process);
+
+        let node = hierarchy.find("top.interconnect.ic_to_pro").unwrap();
+        assert_eq!(("ic_to_pro", Kind::Port), (node.name(), node.kind()));
+        assert_eq!(
+            Kind::Module,
+            hierarchy.find("top.interconnect").unwrap().kind()
+        );
+        assert!(hierarchy.find("top.copro1").is_none());
+        let paths: Vec<&str> = hierarchy.iter().map(Node::path).collect();
+        assert_eq!(
+            vec![
+                "top",
+                "top.ic_to_pro",
+                "top.interconnect",
+                "top.interconnect.ic_to_pro",
+                "top.interconnect.process",
+            ],
+            paths
+        );
+        assert_eq!(
+            "top (module)
+  ic_to_pro (signal)
+  interconnect (module)
+    ic_to_pro (port)
+    process (process)
+",
+            hierarchy.to_string()
+        );
+    }
+}
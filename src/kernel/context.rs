@@ -0,0 +1,81 @@
+//! This module holds the thread local context of the process being evaluated.
+
+use super::process::{ProcessId, Wait};
+use super::Shared;
+use std::cell::RefCell;
+use std::sync::Arc;
+
+thread_local! {
+    static CURRENT: RefCell<Option<Handle>> = const { RefCell::new(None) };
+}
+
+/// Handle on the kernel running the current process.
+#[derive(Clone)]
+pub(crate) struct Handle {
+    pub(crate) shared: Arc<Shared>,
+    /// The process being evaluated.
+    pub(crate) process: ProcessId,
+}
+
+/// Restores the previous context when dropped.
+pub(crate) struct EnterGuard {
+    previous: Option<Handle>,
+}
+
+impl Drop for EnterGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// Set the current kernel and process for the duration of the guard.
+pub(crate) fn enter(shared: Arc<Shared>, process: ProcessId) -> EnterGuard {
+    let previous = CURRENT.with(|current| current.borrow_mut().replace(Handle { shared, process }));
+    EnterGuard { previous }
+}
+
+/// Returns the kernel handle if called from a simulation process.
+pub(crate) fn current() -> Option<Handle> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Restores what the process waited for before the guard, when dropped.
+pub(crate) struct WaitGuard {
+    handle: Handle,
+    previous: Option<Wait>,
+}
+
+impl Drop for WaitGuard {
+    fn drop(&mut self) {
+        self.handle.shared.lock().processes[self.handle.process].waits_on = self.previous.take();
+    }
+}
+
+/// Record what the current process waits for, for the duration of the guard.
+///
+/// A wait nested in an idle wait stays idle.
+fn wait(wait: Wait) -> Option<WaitGuard> {
+    let handle = current()?;
+    let previous = {
+        let mut state = handle.shared.lock();
+        let waits_on = &mut state.processes[handle.process].waits_on;
+        if *waits_on == Some(Wait::Idle) {
+            return None;
+        }
+        waits_on.replace(wait)
+    };
+    Some(WaitGuard { handle, previous })
+}
+
+/// Record that the current process waits on the signal `name` for the duration of the guard, so
+/// that a deadlock can name it.
+pub(crate) fn wait_on(name: Option<&str>) -> Option<WaitGuard> {
+    wait(Wait::Read(name.map(str::to_string)))
+}
+
+/// Record that the current process waits for an event which may never come without a deadlock,
+/// for the duration of the guard.
+pub(crate) fn idle() -> Option<WaitGuard> {
+    wait(Wait::Idle)
+}
@@ -0,0 +1,1295 @@
+//! This module holds the discrete-event simulation kernel.
+//!
+//! The kernel follows the VHDL/SystemC scheduling algorithm:
+//! 1. *Evaluation*: every runnable process is polled until it blocks again.
+//! 2. *Update*: the values written during the evaluation phase are propagated to the signals,
+//!    which wakes up the processes sensitive to them. If any process was woken up, a new
+//!    *delta-cycle* starts at the same simulated time.
+//! 3. *Time advance*: when no process is runnable, the simulated time jumps to the next event of
+//!    the time wheel.
+//!
+//! The simulation ends when no process is runnable and the time wheel is empty.
+//!
+//! The processes of an evaluation phase run either in parallel on the tokio runtime, or one after
+//! the other in a reproducible order, see [`SchedulerMode`].
+
+pub(crate) mod context;
+mod process;
+pub mod scheduler;
+pub mod time;
+pub mod wait;
+
+use crate::error::{Blocked, Deadlock, ElaborationError, Lagged};
+use crate::event::{self, Event};
+use crate::hierarchy::{Direction, Hierarchy, Kind};
+use crate::synth::{self, Entity};
+use crate::trace::{Probe, Trace, Traced, Vcd};
+use futures::future::{BoxFuture, FutureExt};
+use futures::task::noop_waker_ref;
+pub(crate) use process::panic_message;
+use process::{Process, ProcessId, ProcessState, Wait};
+use scheduler::{Order, SchedulerMode};
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
+use std::io;
+use std::panic::resume_unwind;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Waker};
+use std::time::Duration;
+use time::SimTime;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+/// A channel with a value waiting to be propagated in the update phase.
+pub(crate) trait Update: Send {
+    /// Propagate the pending value.
+    fn update(self: Box<Self>);
+}
+
+/// Scheduling phase of the kernel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Phase {
+    Elaboration,
+    Evaluate,
+    Update,
+    Advance,
+    Done,
+}
+
+/// What the kernel does when a reader of a signal misses some of its values.
+///
+/// A reader misses values when a signal changes in several *delta-cycles* between two of its
+/// reads. Only the last value of a signal is kept, so the reader gets that one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LagMode {
+    /// The missed values are skipped silently.
+    #[default]
+    Ignore,
+    /// The missed values are counted per signal, see [`Simulator::lagged`].
+    Count,
+    /// The process reading the signal panics with the [`Lagged`] error.
+    Fatal,
+}
+
+/// Kernel state, shared with the processes and the signals.
+struct State {
+    now: SimTime,
+    delta: u64,
+    phase: Phase,
+    processes: Vec<ProcessState>,
+    /// Processes spawned since the last evaluation phase, which the simulator did not start yet.
+    spawned: Vec<Process>,
+    /// Processes to release at the start of the next evaluation phase.
+    runnable: VecDeque<ProcessId>,
+    /// Number of released processes that did not block yet.
+    active: usize,
+    /// Channels to update at the end of the evaluation phase.
+    updates: Vec<Box<dyn Update>>,
+    /// Wakers to wake at the next *delta-cycle*.
+    delta_notifications: Vec<Waker>,
+    /// Time wheel.
+    timed: BTreeMap<SimTime, Vec<Waker>>,
+    /// Number of *delta-cycles* since the start of the simulation.
+    total_deltas: u64,
+    /// Number of simulated time points with at least one event.
+    time_steps: u64,
+    lag_mode: LagMode,
+    /// Number of values missed by the readers of each signal.
+    lagged: Vec<Lagged>,
+    /// A process called [`stop`].
+    stopped: bool,
+}
+
+impl State {
+    /// Returns the processes blocked reading a signal, if no timed event can wake them up
+    /// anymore.
+    ///
+    /// The processes idle in the sensitivity of a method or reading a signal they drive
+    /// themselves are starved rather than blocked, so they are not a deadlock.
+    fn deadlock(&self) -> Option<Deadlock> {
+        if !self.timed.is_empty() {
+            return None;
+        }
+        let blocked: Vec<Blocked> = self
+            .processes
+            .iter()
+            .filter(|process| !process.finished)
+            .filter_map(|process| match &process.waits_on {
+                Some(Wait::Read(signal)) => Some(Blocked {
+                    process: process.name.clone(),
+                    signal: signal.clone(),
+                }),
+                _ => None,
+            })
+            .collect();
+        if blocked.is_empty() {
+            return None;
+        }
+        Some(Deadlock {
+            blocked,
+            time: self.now,
+        })
+    }
+
+    /// Allow a process to run in the current evaluation phase.
+    fn release(&mut self, id: ProcessId) -> Option<Waker> {
+        let process = &mut self.processes[id];
+        process.queued = false;
+        if process.finished || process.released {
+            return None;
+        }
+        process.released = true;
+        self.active += 1;
+        process.waker.take()
+    }
+}
+
+pub(crate) struct Shared {
+    mode: SchedulerMode,
+    state: Mutex<State>,
+    /// Notified when the last active process blocks.
+    idle: Notify,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .expect("Simulation kernel state poisoned.")
+    }
+
+    /// Mark a process as runnable.
+    ///
+    /// During the evaluation phase of the threaded scheduler the process runs immediately,
+    /// otherwise it is queued for the next evaluation pass.
+    pub(crate) fn schedule(&self, id: ProcessId) {
+        let waker = {
+            let mut state = self.lock();
+            if state.processes[id].finished {
+                return;
+            }
+            if state.phase == Phase::Evaluate && self.mode == SchedulerMode::Threaded {
+                let process = &mut state.processes[id];
+                if process.released {
+                    process.rewake = true;
+                    None
+                } else {
+                    state.release(id)
+                }
+            } else {
+                let process = &mut state.processes[id];
+                if !process.queued {
+                    process.queued = true;
+                    state.runnable.push_back(id);
+                }
+                None
+            }
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Register a new process, which runs in the next evaluation phase.
+    fn spawn(self: &Arc<Self>, name: Option<String>, future: BoxFuture<'static, ()>) {
+        let mut state = self.lock();
+        let id = state.processes.len();
+        state.processes.push(ProcessState {
+            name,
+            queued: true,
+            ..Default::default()
+        });
+        state.runnable.push_back(id);
+        state.spawned.push(Process::new(self.clone(), id, future));
+    }
+
+    /// Request a channel update at the end of the current evaluation phase.
+    pub(crate) fn request_update(&self, update: Box<dyn Update>) {
+        self.lock().updates.push(update);
+    }
+
+    /// Returns the current simulated time.
+    pub(crate) fn now(&self) -> SimTime {
+        self.lock().now
+    }
+
+    /// Report the values of a signal missed by one of its readers, according to the [`LagMode`].
+    ///
+    /// # Panics
+    /// Panics with the [`Lagged`] error in the [`LagMode::Fatal`] mode.
+    pub(crate) fn report_lag(&self, signal: Option<&str>, skipped: u64) {
+        let mut state = self.lock();
+        match state.lag_mode {
+            LagMode::Ignore => {}
+            LagMode::Count => {
+                let signal = signal.map(str::to_string);
+                match state
+                    .lagged
+                    .iter_mut()
+                    .find(|lagged| lagged.signal == signal)
+                {
+                    Some(lagged) => lagged.skipped += skipped,
+                    None => state.lagged.push(Lagged { signal, skipped }),
+                }
+            }
+            LagMode::Fatal => {
+                drop(state);
+                let lagged = Lagged {
+                    signal: signal.map(str::to_string),
+                    skipped,
+                };
+                panic!("{}", lagged);
+            }
+        }
+    }
+
+    /// Evaluation phase: run the process tasks until all of them are blocked.
+    async fn evaluate(&self) {
+        let wakers = {
+            let mut state = self.lock();
+            state.phase = Phase::Evaluate;
+            let runnable: Vec<ProcessId> = state.runnable.drain(..).collect();
+            runnable
+                .into_iter()
+                .filter_map(|id| state.release(id))
+                .collect::<Vec<_>>()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+
+        loop {
+            {
+                let mut state = self.lock();
+                if state.active == 0 {
+                    state.phase = Phase::Update;
+                    return;
+                }
+            }
+            self.idle.notified().await;
+        }
+    }
+
+    /// Update phase: propagate the written values.
+    ///
+    /// Returns true if a process must run in a new *delta-cycle*.
+    fn update(&self) -> bool {
+        let (updates, notifications) = {
+            let mut state = self.lock();
+            (
+                std::mem::take(&mut state.updates),
+                std::mem::take(&mut state.delta_notifications),
+            )
+        };
+        updates.into_iter().for_each(|update| update.update());
+        notifications.into_iter().for_each(Waker::wake);
+
+        let mut state = self.lock();
+        if state.runnable.is_empty() {
+            false
+        } else {
+            state.delta += 1;
+            state.total_deltas += 1;
+            true
+        }
+    }
+
+    /// Time advance phase: move to the next event of the time wheel.
+    ///
+    /// Returns false if there is no event left, or if the next event is after `limit`. In the
+    /// latter case the simulated time stops at `limit`, unless `limit` is already past.
+    fn advance(&self, limit: Option<SimTime>) -> bool {
+        let wakers = {
+            let mut state = self.lock();
+            state.phase = Phase::Advance;
+            match state.timed.first_key_value() {
+                None => return false,
+                Some((&time, _)) => {
+                    if let Some(limit) = limit.filter(|&limit| time > limit) {
+                        if limit > state.now {
+                            state.now = limit;
+                            state.delta = 0;
+                        }
+                        return false;
+                    }
+                }
+            }
+            let (time, wakers) = state
+                .timed
+                .pop_first()
+                .expect("The time wheel is not empty.");
+            state.now = time;
+            state.delta = 0;
+            state.time_steps += 1;
+            wakers
+        };
+        wakers.into_iter().for_each(Waker::wake);
+        true
+    }
+}
+
+/// The simulator owns the processes and schedules them in *delta-cycles*.
+pub struct Simulator {
+    shared: Arc<Shared>,
+    processes: Vec<Process>,
+    /// Tasks running the processes with the threaded scheduler.
+    tasks: Vec<(ProcessId, JoinHandle<()>)>,
+    /// Order of the processes with the deterministic scheduler.
+    order: Option<Order>,
+    /// Signals registered for tracing.
+    traced: Vec<Traced>,
+    /// Output of the waveforms of the traced signals.
+    waveform: Option<Box<dyn io::Write + Send>>,
+    /// Writer of the waveforms during the simulation.
+    vcd: Option<Vcd>,
+    /// Entities registered for synthesis.
+    entities: Vec<Entity>,
+    /// Objects registered in the design.
+    hierarchy: Hierarchy,
+    /// Callbacks of the end of the elaboration, the start and the end of the simulation.
+    end_of_elaboration: Vec<Callback>,
+    start_of_simulation: Vec<Callback>,
+    end_of_simulation: Vec<Callback>,
+}
+
+/// Function called by the simulator when it enters a new phase.
+type Callback = Box<dyn FnOnce(&Simulator) + Send>;
+
+impl Simulator {
+    /// Construct a new simulator at the start of its elaboration phase.
+    ///
+    /// The processes are run by the [`SchedulerMode::Threaded`] scheduler.
+    pub fn new() -> Self {
+        Self::with_scheduler(SchedulerMode::Threaded)
+    }
+
+    /// Construct a new simulator running its processes with the given scheduler.
+    pub fn with_scheduler(mode: SchedulerMode) -> Self {
+        Simulator {
+            shared: Arc::new(Shared {
+                mode,
+                state: Mutex::new(State {
+                    now: SimTime::ZERO,
+                    delta: 0,
+                    phase: Phase::Elaboration,
+                    processes: Vec::new(),
+                    spawned: Vec::new(),
+                    runnable: VecDeque::new(),
+                    active: 0,
+                    updates: Vec::new(),
+                    delta_notifications: Vec::new(),
+                    timed: BTreeMap::new(),
+                    total_deltas: 0,
+                    time_steps: 0,
+                    lag_mode: LagMode::Ignore,
+                    lagged: Vec::new(),
+                    stopped: false,
+                }),
+                idle: Notify::new(),
+            }),
+            processes: Vec::new(),
+            tasks: Vec::new(),
+            order: None,
+            traced: Vec::new(),
+            waveform: None,
+            vcd: None,
+            entities: Vec::new(),
+            hierarchy: Hierarchy::new(),
+            end_of_elaboration: Vec::new(),
+            start_of_simulation: Vec::new(),
+            end_of_simulation: Vec::new(),
+        }
+    }
+
+    /// Register a process.
+    ///
+    /// Every process runs once at the start of the simulation.
+    pub fn spawn<F>(&mut self, process: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.spawn_process(None, process);
+    }
+
+    /// Register a process under a hierarchical path, such as `"top.cpu.fetch"`.
+    ///
+    /// The process is added to the [`Hierarchy`] of the design.
+    pub fn spawn_named<F>(&mut self, path: &str, process: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.hierarchy.insert(path, Kind::Process);
+        self.spawn_process(Some(path.to_string()), process);
+    }
+
+    /// Register a method process under a hierarchical path, with its static sensitivity, such as
+    /// `[clk.pos(), rst.changed()]`.
+    ///
+    /// The method runs once at the start of the simulation, then every time one of the events is
+    /// triggered, see [`event::method`].
+    pub fn spawn_method<I, F>(&mut self, path: &str, sensitivity: I, method: F)
+    where
+        I: IntoIterator<Item = Event>,
+        F: FnMut() + Send + 'static,
+    {
+        let events: Vec<Event> = sensitivity.into_iter().collect();
+        self.spawn_named(path, event::method(events, method));
+    }
+
+    fn spawn_process<F>(&mut self, name: Option<String>, process: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.shared.spawn(name, process.boxed());
+    }
+
+    /// Register a port, signal or module instance under a hierarchical path, such as
+    /// `"top.cpu.clk"`.
+    pub fn register(&mut self, path: &str, kind: Kind) {
+        self.hierarchy.insert(path, kind);
+    }
+
+    /// Register the connection of a port to a signal, such as `"top.cpu.clk"` to `"top.clk"`.
+    ///
+    /// The port and the signal are added to the [`Hierarchy`] of the design, and the connections
+    /// are checked by [`Simulator::elaborate`].
+    pub fn connect(&mut self, port: &str, signal: &str, direction: Direction) {
+        self.hierarchy.connect(port, signal, direction);
+    }
+
+    /// Check the connections of the ports and signals registered in the design.
+    ///
+    /// This is done at the start of the first run, see [`Hierarchy::check`] for the errors.
+    pub fn elaborate(&self) -> Result<(), Vec<ElaborationError>> {
+        let errors = self.hierarchy.check();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Returns the tree of the objects registered in the design.
+    pub fn hierarchy(&self) -> &Hierarchy {
+        &self.hierarchy
+    }
+
+    /// Register a signal to trace under a hierarchical name, such as `"top.cpu.clk"`.
+    ///
+    /// The traced signals are written to the waveform given to [`Simulator::dump_vcd`]. Without a
+    /// waveform they are dropped when the simulation starts, so that they do not keep the signals
+    /// open.
+    pub fn trace<T: Trace>(&mut self, name: &str, signal: &impl Probe<T>) {
+        self.traced.push(Traced::new(name, signal.probe()));
+    }
+
+    /// Register the LLHD IR of an `#[entity]` function as part of the synthesized design.
+    ///
+    /// An entity is registered once, even if it is instantiated many times.
+    pub fn synthesize(&mut self, entity: Entity) {
+        if !self.entities.contains(&entity) {
+            self.entities.push(entity);
+        }
+    }
+
+    /// Returns the LLHD module of the registered entities, linked and verified.
+    pub fn llhd_module(&self) -> Result<llhd::ir::Module, String> {
+        synth::module(&self.entities)
+    }
+
+    /// Write the waveforms of the traced signals in the Value Change Dump format during the next
+    /// run.
+    ///
+    /// # Panics
+    /// The run panics if the waveforms cannot be written.
+    pub fn dump_vcd<W: io::Write + Send + 'static>(&mut self, out: W) {
+        self.waveform = Some(Box::new(out));
+    }
+
+    /// Set what happens when a reader of a signal misses some of its values.
+    ///
+    /// The values are skipped silently by default, see [`LagMode`].
+    pub fn set_lag_mode(&mut self, mode: LagMode) {
+        self.shared.lock().lag_mode = mode;
+    }
+
+    /// Returns the number of values missed by the readers of each signal so far, in the
+    /// [`LagMode::Count`] mode.
+    ///
+    /// The signals without a hierarchical path are counted together.
+    pub fn lagged(&self) -> Vec<Lagged> {
+        self.shared.lock().lagged.clone()
+    }
+
+    /// Returns the current simulated time.
+    pub fn time(&self) -> SimTime {
+        self.shared.now()
+    }
+
+    /// Returns the number of *delta-cycles* elapsed at the current simulated time.
+    pub fn delta_count(&self) -> u64 {
+        self.shared.lock().delta
+    }
+
+    /// Returns the counters of the work done by the kernel so far.
+    pub fn statistics(&self) -> Statistics {
+        let state = self.shared.lock();
+        Statistics {
+            processes: state.processes.len(),
+            time_steps: state.time_steps,
+            delta_cycles: state.total_deltas,
+            lagged_values: state.lagged.iter().map(|lagged| lagged.skipped).sum(),
+        }
+    }
+
+    /// Register a function called once the design is elaborated, before the first run.
+    pub fn on_end_of_elaboration<F: FnOnce(&Simulator) + Send + 'static>(&mut self, callback: F) {
+        self.end_of_elaboration.push(Box::new(callback));
+    }
+
+    /// Register a function called right before the processes run for the first time.
+    pub fn on_start_of_simulation<F: FnOnce(&Simulator) + Send + 'static>(&mut self, callback: F) {
+        self.start_of_simulation.push(Box::new(callback));
+    }
+
+    /// Register a function called once the simulation is over, with the statistics of the run.
+    ///
+    /// A run which pauses at its limit or condition, see [`Simulator::run_until`], does not end
+    /// the simulation: the functions are only called by the run which ends it.
+    pub fn on_end_of_simulation<F: FnOnce(&Simulator) + Send + 'static>(&mut self, callback: F) {
+        self.end_of_simulation.push(Box::new(callback));
+    }
+
+    /// Run the simulation until no process can be woken up anymore, or until a process calls
+    /// [`stop`].
+    ///
+    /// With the [`SchedulerMode::Threaded`] scheduler, every process is run as a tokio task, so
+    /// this must be called from a tokio runtime. Returns the simulated time at the end of the
+    /// simulation.
+    ///
+    /// # Panics
+    /// Panics with the errors of the elaboration, see [`Simulator::elaborate`], or with the
+    /// [`Deadlock`] listing the processes left blocked reading a signal when no timed event can
+    /// wake them up.
+    pub async fn run(&mut self) -> SimTime {
+        self.run_to(None, None).await
+    }
+
+    /// Run the simulation until no process can be woken up anymore, or until the simulated time
+    /// reaches `limit`.
+    ///
+    /// The events scheduled after `limit` are kept, so that the next run resumes the simulation
+    /// from `limit`. Returns the simulated time at the end of the run, which is the current time
+    /// if `limit` is already past.
+    ///
+    /// # Panics
+    /// Panics as [`Simulator::run`]. The processes blocked at `limit` are not a deadlock as long
+    /// as a timed event is pending.
+    pub async fn run_until(&mut self, limit: SimTime) -> SimTime {
+        self.run_to(Some(limit), None).await
+    }
+
+    /// Run the simulation for `duration` from the current simulated time, as
+    /// [`Simulator::run_until`], so that it can be called repeatedly.
+    pub async fn run_for(&mut self, duration: Duration) -> SimTime {
+        self.run_to(Some(self.time() + duration), None).await
+    }
+
+    /// Run the simulation until `condition` returns true, as [`Simulator::run`].
+    ///
+    /// The condition is checked once the signals are stable at each simulated time, that is after
+    /// the last *delta-cycle* of the time step. The next run resumes the simulation from there.
+    pub async fn run_until_condition<F>(&mut self, mut condition: F) -> SimTime
+    where
+        F: FnMut() -> bool + Send,
+    {
+        self.run_to(None, Some(&mut condition)).await
+    }
+
+    async fn run_to(
+        &mut self,
+        limit: Option<SimTime>,
+        mut condition: Option<&mut (dyn FnMut() -> bool + Send)>,
+    ) -> SimTime {
+        let phase = self.shared.lock().phase;
+        match phase {
+            Phase::Done => return self.time(),
+            Phase::Elaboration => {}
+            _ => return self.resume(limit, condition).await,
+        }
+        if let Err(errors) = self.elaborate() {
+            panic!("{}", elaboration_failed(&errors));
+        }
+        self.call(|sim| &mut sim.end_of_elaboration);
+        self.call(|sim| &mut sim.start_of_simulation);
+        self.order = match self.shared.mode {
+            SchedulerMode::Threaded => None,
+            SchedulerMode::Deterministic { seed } => Some(Order::new(seed)),
+        };
+        let traced = std::mem::take(&mut self.traced);
+        self.vcd = self
+            .waveform
+            .take()
+            .map(|out| written(Vcd::new(out, traced)));
+        self.resume(limit, condition.take()).await
+    }
+
+    /// Run the simulation from where the last run paused.
+    async fn resume(
+        &mut self,
+        limit: Option<SimTime>,
+        mut condition: Option<&mut (dyn FnMut() -> bool + Send)>,
+    ) -> SimTime {
+        let ended = loop {
+            // Start the processes spawned during the elaboration or the last delta-cycle.
+            let spawned = std::mem::take(&mut self.shared.lock().spawned);
+            match self.order {
+                None => self.tasks.extend(
+                    spawned
+                        .into_iter()
+                        .map(|process| (process.id(), tokio::task::spawn(process))),
+                ),
+                Some(_) => self.processes.extend(spawned),
+            }
+            match self.order.take() {
+                None => {
+                    self.shared.evaluate().await;
+                    self.join_finished().await;
+                }
+                Some(mut order) => {
+                    self.evaluate_inline(&mut order);
+                    self.order = Some(order);
+                }
+            }
+            let delta = self.shared.update();
+            let stopped = self.shared.lock().stopped;
+            if delta && !stopped {
+                continue;
+            }
+            let now = self.time();
+            if let Some(vcd) = self.vcd.as_mut() {
+                written(vcd.sample(now));
+            }
+            if stopped {
+                break true;
+            }
+            if condition.as_mut().is_some_and(|condition| condition()) {
+                break false;
+            }
+            if !self.shared.advance(limit) {
+                // The events after `limit` are kept for the next run.
+                break self.shared.lock().timed.is_empty();
+            }
+        };
+
+        let now = self.time();
+        if let Some(vcd) = self.vcd.as_mut() {
+            written(vcd.finish(now));
+        }
+        if !ended {
+            return self.time();
+        }
+        // A stopped simulation leaves its processes blocked on purpose.
+        let deadlock = if self.shared.lock().stopped {
+            None
+        } else {
+            self.shared.lock().deadlock()
+        };
+        self.shutdown();
+        self.call(|sim| &mut sim.end_of_simulation);
+        if let Some(deadlock) = deadlock {
+            panic!("{}", deadlock);
+        }
+        self.time()
+    }
+
+    /// Drop the processes and the events waiting for them, once the simulation is over.
+    fn shutdown(&mut self) {
+        {
+            let mut state = self.shared.lock();
+            state.phase = Phase::Done;
+            state.spawned.clear();
+            state.runnable.clear();
+            state.timed.clear();
+            state.delta_notifications.clear();
+        }
+        self.tasks.drain(..).for_each(|(_, task)| task.abort());
+        self.processes.clear();
+    }
+
+    /// Wait for the tasks of the processes which finished with the threaded scheduler, and raise
+    /// the panic of a process again, as the deterministic scheduler does.
+    async fn join_finished(&mut self) {
+        let (finished, running): (Vec<_>, Vec<_>) = {
+            let state = self.shared.lock();
+            std::mem::take(&mut self.tasks)
+                .into_iter()
+                .partition(|(id, _)| state.processes[*id].finished)
+        };
+        self.tasks = running;
+        for (_, task) in finished {
+            if let Err(err) = task.await {
+                if err.is_panic() {
+                    resume_unwind(err.into_panic());
+                }
+            }
+        }
+    }
+
+    /// Call the callbacks of a phase once.
+    fn call(&mut self, callbacks: fn(&mut Simulator) -> &mut Vec<Callback>) {
+        for callback in std::mem::take(callbacks(self)) {
+            callback(self);
+        }
+    }
+
+    /// Evaluation phase of the deterministic scheduler: poll the processes on this thread until
+    /// all of them are blocked.
+    fn evaluate_inline(&mut self, order: &mut Order) {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        loop {
+            let started = self.processes.len();
+            let mut runnable: Vec<ProcessId> = {
+                let mut state = self.shared.lock();
+                state.phase = Phase::Evaluate;
+                // The processes spawned in this evaluation phase run in the next one.
+                let (runnable, spawned): (Vec<ProcessId>, Vec<ProcessId>) =
+                    state.runnable.drain(..).partition(|&id| id < started);
+                state.runnable.extend(spawned);
+                for &id in runnable.iter() {
+                    state.processes[id].queued = false;
+                }
+                if runnable.is_empty() {
+                    state.phase = Phase::Update;
+                    return;
+                }
+                runnable
+            };
+            order.arrange(&mut runnable);
+
+            for id in runnable {
+                // The waker of a deterministic process is never used, it is queued instead.
+                let _ = self.shared.lock().release(id);
+                let _ = Pin::new(&mut self.processes[id]).poll(&mut cx);
+            }
+        }
+    }
+}
+
+/// Returns the message listing the errors of a failed elaboration, one per line.
+pub(crate) fn elaboration_failed(errors: &[ElaborationError]) -> String {
+    let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+    format!("The elaboration failed:\n{}", errors.join("\n"))
+}
+
+/// Unwrap the result of a waveform write.
+fn written<T>(result: io::Result<T>) -> T {
+    result.unwrap_or_else(|err| panic!("Unable to write the waveforms: {}", err))
+}
+
+/// Counters of the work done by a [`Simulator`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Statistics {
+    /// Number of registered processes.
+    pub processes: usize,
+    /// Number of simulated time points with at least one event, not counting the start.
+    pub time_steps: u64,
+    /// Number of *delta-cycles* since the start of the simulation, not counting the first
+    /// evaluation phase of every time step.
+    pub delta_cycles: u64,
+    /// Number of values missed by the readers of the signals, in the [`LagMode::Count`] mode.
+    pub lagged_values: u64,
+}
+
+impl Drop for Simulator {
+    /// Drop the processes, which hold the kernel state.
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl Default for Simulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Spawn a new process from a simulation process. It runs for the first time in the next
+/// *delta-cycle*.
+///
+/// # Panics
+/// Panics if it is not called from a simulation process, use [`Simulator::spawn`] instead.
+pub fn spawn<F>(process: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    context::current()
+        .expect("Processes must be spawned from a simulation process, or with `Simulator::spawn`.")
+        .shared
+        .spawn(None, process.boxed());
+}
+
+/// Stop the simulation at the end of the current *delta-cycle*.
+///
+/// The values written in the *delta-cycle* are still propagated, then
+/// [`Simulator::run`] returns without running the processes again.
+///
+/// # Panics
+/// Panics if it is not called from a simulation process.
+pub fn stop() {
+    context::current()
+        .expect("The simulation must be stopped from a simulation process.")
+        .shared
+        .lock()
+        .stopped = true;
+}
+
+/// Returns the current simulated time.
+///
+/// Outside of a simulation process, this is always [`SimTime::ZERO`].
+pub fn now() -> SimTime {
+    context::current()
+        .map(|handle| handle.shared.now())
+        .unwrap_or(SimTime::ZERO)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::NBReadError;
+    use crate::signal::signal;
+    use crate::{Read, Write};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_nb_write_visible_next_delta() {
+        let mut sim = Simulator::new();
+        let (tx, mut rx) = signal();
+        let (done_tx, mut done_rx) = signal();
+        sim.spawn(async move {
+            tx.nb_write(42).unwrap();
+            assert!(matches!(rx.nb_read(), Err(NBReadError::Empty)));
+            assert_eq!(42, rx.b_read().await.unwrap_or(0));
+            done_tx.nb_write(true).unwrap();
+        });
+        let checked = Arc::new(AtomicU64::new(0));
+        let check = checked.clone();
+        sim.spawn(async move {
+            if let Ok(true) = done_rx.b_read().await {
+                check.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        sim.run().await;
+        assert_eq!(1, checked.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_processes_see_same_delta() {
+        let mut sim = Simulator::new();
+        let (tx, rx) = signal::<u32>();
+        let reads = Arc::new(AtomicU64::new(0));
+        for _ in 0..8 {
+            let mut rx = tx.subscribe();
+            let reads = reads.clone();
+            sim.spawn(async move {
+                // Every reader observes the value written in the first delta-cycle.
+                assert_eq!(7, rx.b_read().await.unwrap_or(0));
+                reads.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        drop(rx);
+        sim.spawn(async move {
+            tx.nb_write(7).unwrap();
+        });
+        assert_eq!(SimTime::ZERO, sim.run().await);
+        assert_eq!(8, reads.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_delta_count() {
+        let mut sim = Simulator::new();
+        let (ping_tx, mut ping_rx) = signal();
+        let (pong_tx, mut pong_rx) = signal();
+        sim.spawn(async move {
+            for i in 1..=3 {
+                ping_tx.nb_write(i).unwrap();
+                if pong_rx.b_read().await.is_err() {
+                    return;
+                }
+            }
+        });
+        sim.spawn(async move {
+            while let Ok(i) = ping_rx.b_read().await {
+                pong_tx.nb_write(i).unwrap();
+            }
+        });
+        sim.run().await;
+        assert_eq!(6, sim.delta_count());
+        assert_eq!(
+            Statistics {
+                processes: 2,
+                time_steps: 0,
+                delta_cycles: 6,
+                lagged_values: 0,
+            },
+            sim.statistics()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_until() {
+        let mut sim = Simulator::with_scheduler(SchedulerMode::Deterministic { seed: None });
+        let ticks = Arc::new(AtomicU64::new(0));
+        let counter = ticks.clone();
+        sim.spawn(async move {
+            loop {
+                wait::wait_for(Duration::from_nanos(10)).await;
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        let limit = SimTime::from_duration(Duration::from_nanos(35));
+        assert_eq!(limit, sim.run_until(limit).await);
+        assert_eq!(3, ticks.load(Ordering::SeqCst));
+        assert_eq!(3, sim.statistics().time_steps);
+    }
+
+    #[tokio::test]
+    async fn test_run_for() {
+        for mode in [
+            SchedulerMode::Threaded,
+            SchedulerMode::Deterministic { seed: None },
+        ] {
+            let mut sim = Simulator::with_scheduler(mode);
+            let ticks = Arc::new(AtomicU64::new(0));
+            let counter = ticks.clone();
+            sim.spawn(async move {
+                for _ in 0..5 {
+                    wait::wait_for(Duration::from_nanos(10)).await;
+                    counter.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+            assert_eq!(25, sim.run_for(Duration::from_nanos(25)).await.as_nanos());
+            assert_eq!(2, ticks.load(Ordering::SeqCst));
+            // The second run resumes the processes from where the first one paused.
+            assert_eq!(35, sim.run_for(Duration::from_nanos(10)).await.as_nanos());
+            assert_eq!(3, ticks.load(Ordering::SeqCst));
+            assert_eq!(50, sim.run().await.as_nanos());
+            assert_eq!(5, ticks.load(Ordering::SeqCst));
+            assert_eq!(5, sim.statistics().time_steps);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_until_past() {
+        let mut sim = Simulator::with_scheduler(SchedulerMode::Deterministic { seed: None });
+        sim.spawn(async {
+            for _ in 0..3 {
+                wait::wait_for(Duration::from_nanos(10)).await;
+            }
+        });
+        let limit = SimTime::ZERO + Duration::from_nanos(15);
+        assert_eq!(15, sim.run_until(limit).await.as_nanos());
+        // The time does not go back to a limit already past.
+        let past = SimTime::ZERO + Duration::from_nanos(5);
+        assert_eq!(15, sim.run_until(past).await.as_nanos());
+        assert_eq!(15, sim.time().as_nanos());
+        assert_eq!(30, sim.run().await.as_nanos());
+    }
+
+    #[tokio::test]
+    async fn test_run_until_condition() {
+        let mut sim = Simulator::with_scheduler(SchedulerMode::Deterministic { seed: None });
+        let ticks = Arc::new(AtomicU64::new(0));
+        let counter = ticks.clone();
+        sim.spawn(async move {
+            loop {
+                wait::wait_for(Duration::from_nanos(10)).await;
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        let end = sim
+            .run_until_condition(|| ticks.load(Ordering::SeqCst) == 4)
+            .await;
+        assert_eq!(40, end.as_nanos());
+    }
+
+    #[tokio::test]
+    async fn test_stop() {
+        for mode in [
+            SchedulerMode::Threaded,
+            SchedulerMode::Deterministic { seed: None },
+        ] {
+            let mut sim = Simulator::with_scheduler(mode);
+            let (tx, mut rx) = signal();
+            let clock = crate::clock::Clock::new(Duration::from_nanos(10));
+            let _clk = clock.subscribe();
+            sim.spawn(async move {
+                wait::wait_for(Duration::from_nanos(20)).await;
+                tx.nb_write(1).unwrap();
+                stop();
+                // The process is not run again.
+                crate::wait().await.unwrap();
+                tx.nb_write(2).unwrap();
+            });
+            sim.spawn(clock.process());
+            assert_eq!(20, sim.run().await.as_nanos());
+            assert_eq!(Ok(1), rx.nb_read());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_phase_callbacks() {
+        let mut sim = Simulator::with_scheduler(SchedulerMode::Deterministic { seed: None });
+        let phases = Arc::new(Mutex::new(Vec::new()));
+        let log = |phases: &Arc<Mutex<Vec<String>>>, phase: &'static str| {
+            let phases = phases.clone();
+            move |sim: &Simulator| {
+                phases
+                    .lock()
+                    .unwrap()
+                    .push(format!("{} at {}", phase, sim.time()));
+            }
+        };
+        sim.on_end_of_simulation(log(&phases, "end of simulation"));
+        sim.on_start_of_simulation(log(&phases, "start of simulation"));
+        sim.on_end_of_elaboration(log(&phases, "end of elaboration"));
+        let running = phases.clone();
+        sim.spawn(async move {
+            running.lock().unwrap().push("process".to_string());
+            wait::wait_for(Duration::from_nanos(10)).await;
+        });
+        sim.run().await;
+        assert_eq!(
+            vec![
+                "end of elaboration at 0 ns",
+                "start of simulation at 0 ns",
+                "process",
+                "end of simulation at 10 ns",
+            ],
+            *phases.lock().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "The elaboration failed:
+The input port `top.cpu.irq` reads the signal `top.irq`, which has no driver.")]
+    async fn test_elaboration_errors() {
+        let mut sim = Simulator::with_scheduler(SchedulerMode::Deterministic { seed: None });
+        sim.connect("top.cpu.irq", "top.irq", Direction::In);
+        assert_eq!(
+            Kind::Signal,
+            sim.hierarchy().find("top.irq").unwrap().kind()
+        );
+        assert_eq!(1, sim.elaborate().unwrap_err().len());
+        sim.run().await;
+    }
+
+    #[tokio::test]
+    async fn test_spawn_from_process() {
+        for mode in [
+            SchedulerMode::Threaded,
+            SchedulerMode::Deterministic { seed: None },
+        ] {
+            let mut sim = Simulator::with_scheduler(mode);
+            let (tx, mut rx) = signal();
+            let reads = Arc::new(AtomicU64::new(0));
+            let read = reads.clone();
+            sim.spawn(async move {
+                wait::wait_for(Duration::from_nanos(10)).await;
+                spawn(async move {
+                    if let Ok(7) = rx.b_read().await {
+                        read.fetch_add(1, Ordering::SeqCst);
+                    }
+                });
+                crate::wait().await.unwrap();
+                tx.nb_write(7).unwrap();
+            });
+            assert_eq!(10, sim.run().await.as_nanos());
+            assert_eq!(1, reads.load(Ordering::SeqCst));
+            assert_eq!(2, sim.statistics().processes);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deadlock() {
+        for mode in [
+            SchedulerMode::Threaded,
+            SchedulerMode::Deterministic { seed: None },
+        ] {
+            let mut sim = Simulator::with_scheduler(mode);
+            let (request, mut request_rx) = crate::signal::named::<bool>("top.request");
+            let (ready, mut ready_rx) = crate::signal::named::<bool>("top.ready");
+            sim.spawn_named("top.ic.process", async move {
+                crate::wait_for(Duration::from_nanos(10)).await;
+                let _ = ready_rx.b_read().await;
+                request.nb_write(true).unwrap();
+            });
+            sim.spawn_named("top.copro.process", async move {
+                let _ = request_rx.b_read().await;
+                ready.nb_write(true).unwrap();
+            });
+            sim.spawn(async {
+                crate::wait().await.unwrap();
+            });
+
+            let panic = std::panic::AssertUnwindSafe(sim.run())
+                .catch_unwind()
+                .await
+                .unwrap_err();
+            assert_eq!(
+                "Deadlock at 10 ns, the blocked processes are:
+- `top.ic.process` waits on the signal `top.ready`
+- `top.copro.process` waits on the signal `top.request`",
+                process::panic_message(&*panic)
+            );
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_method_idle() {
+        for mode in [
+            SchedulerMode::Threaded,
+            SchedulerMode::Deterministic { seed: None },
+        ] {
+            let mut sim = Simulator::with_scheduler(mode);
+            let (count, mut count_rx) = crate::signal::named::<u32>("top.count");
+            let (_enable, enable_rx) = crate::signal::named::<bool>("top.enable");
+            sim.spawn_named("top.counter.process", async move {
+                for i in 1..=3 {
+                    count.nb_write(i).unwrap();
+                    crate::wait_for(Duration::from_nanos(10)).await;
+                }
+            });
+            // The method waits on its sensitivity after the counter ends, it is not blocked.
+            sim.spawn_method("top.monitor.method", [enable_rx.changed()], move || {
+                let _ = count_rx.nb_read();
+            });
+            assert_eq!(30, sim.run().await.as_nanos());
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_spawn_named() {
+        for mode in [
+            SchedulerMode::Threaded,
+            SchedulerMode::Deterministic { seed: None },
+        ] {
+            let mut sim = Simulator::with_scheduler(mode);
+            sim.register("top.cpu.alu", Kind::Module);
+            sim.spawn_named("top.cpu.fetch", async {
+                wait::wait_for(Duration::from_nanos(10)).await;
+                panic!("Bad opcode.");
+            });
+            sim.spawn(async {
+                loop {
+                    wait::wait_for(Duration::from_nanos(10)).await;
+                }
+            });
+            assert_eq!(
+                Kind::Process,
+                sim.hierarchy().find("top.cpu.fetch").unwrap().kind()
+            );
+            assert_eq!(2, sim.hierarchy().find("top.cpu").unwrap().children().len());
+
+            // The panic stops the simulation, even while the other process runs forever.
+            let panic = std::panic::AssertUnwindSafe(sim.run())
+                .catch_unwind()
+                .await
+                .unwrap_err();
+            assert_eq!(
+                "The process `top.cpu.fetch` panicked: Bad opcode.",
+                process::panic_message(&*panic)
+            );
+            assert_eq!(10, sim.time().as_nanos());
+        }
+    }
+
+    /// Writes three values in three *delta-cycles*, which are read 10 ns later.
+    fn lagging(mode: LagMode) -> Simulator {
+        let mut sim = Simulator::with_scheduler(SchedulerMode::Deterministic { seed: None });
+        sim.set_lag_mode(mode);
+        let (tx, mut rx) = crate::signal::named::<u32>("top.data");
+        sim.spawn(async move {
+            for i in 1..=3 {
+                tx.nb_write(i).unwrap();
+                crate::wait().await.unwrap();
+            }
+        });
+        sim.spawn(async move {
+            wait::wait_for(Duration::from_nanos(10)).await;
+            assert_eq!(3, rx.b_read().await.unwrap_or(0));
+        });
+        sim
+    }
+
+    #[tokio::test]
+    async fn test_lag_count() {
+        let mut sim = lagging(LagMode::Count);
+        sim.run().await;
+        assert_eq!(
+            vec![Lagged {
+                signal: Some("top.data".to_string()),
+                skipped: 2,
+            }],
+            sim.lagged()
+        );
+        assert_eq!(2, sim.statistics().lagged_values);
+
+        let mut sim = lagging(LagMode::Ignore);
+        sim.run().await;
+        assert!(sim.lagged().is_empty());
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "A reader of the signal `top.data` missed 2 values.")]
+    async fn test_lag_fatal() {
+        lagging(LagMode::Fatal).run().await;
+    }
+
+    /// Returns the order in which the processes ran in each *delta-cycle*.
+    async fn trace_order(mode: SchedulerMode) -> Vec<Vec<usize>> {
+        let mut sim = Simulator::with_scheduler(mode);
+        let trace = Arc::new(std::sync::Mutex::new(vec![Vec::new(); 3]));
+        let (tx, rx) = signal::<u32>();
+        for id in 0..8 {
+            let trace = trace.clone();
+            let mut rx = tx.subscribe();
+            let tx = tx.clone();
+            sim.spawn(async move {
+                for delta in 0..3 {
+                    trace.lock().unwrap()[delta].push(id);
+                    tx.nb_write(delta as u32 + 1).unwrap();
+                    if rx.b_read().await.is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+        drop((tx, rx));
+        sim.run().await;
+        let trace = trace.lock().unwrap().clone();
+        trace
+    }
+
+    #[tokio::test]
+    async fn test_deterministic_spawn_order() {
+        let order = trace_order(SchedulerMode::Deterministic { seed: None }).await;
+        assert_eq!(vec![(0..8).collect::<Vec<_>>(); 3], order);
+    }
+
+    #[tokio::test]
+    async fn test_deterministic_seeded_order() {
+        let seeded = SchedulerMode::Deterministic { seed: Some(7) };
+        let order = trace_order(seeded).await;
+        assert_eq!(order, trace_order(seeded).await);
+        assert_ne!(
+            order,
+            trace_order(SchedulerMode::Deterministic { seed: None }).await
+        );
+        for delta in order {
+            let mut sorted = delta.clone();
+            sorted.sort_unstable();
+            assert_eq!((0..8).collect::<Vec<_>>(), sorted);
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! This module holds the process wrapper that lets the kernel gate the evaluation of each process.
+
+use super::{context, Shared};
+use futures::future::BoxFuture;
+use futures::task::{waker, ArcWake};
+use std::any::Any;
+use std::future::Future;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+
+/// Index of a process in the kernel.
+pub(crate) type ProcessId = usize;
+
+/// Scheduling state of a process, owned by the kernel.
+#[derive(Default)]
+pub(crate) struct ProcessState {
+    /// Hierarchical path of the process, if it was spawned with one.
+    pub(crate) name: Option<String>,
+    /// The process is allowed to run in the current evaluation phase.
+    pub(crate) released: bool,
+    /// The process was woken up while it was running.
+    pub(crate) rewake: bool,
+    /// The process is waiting in the runnable queue for the next evaluation phase.
+    pub(crate) queued: bool,
+    /// The process future has completed.
+    pub(crate) finished: bool,
+    /// What the process waits for, if it is suspended in a wait known to the kernel.
+    pub(crate) waits_on: Option<Wait>,
+    /// Waker of the task running the process.
+    pub(crate) waker: Option<Waker>,
+}
+
+/// Wait of a suspended process, which tells whether it takes part in a deadlock.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Wait {
+    /// A blocking read of a signal, named by its hierarchical path if it has one.
+    Read(Option<String>),
+    /// A wait that is not a deadlock if it never ends, such as the sensitivity of a method or a
+    /// read of a signal the process drives itself.
+    Idle,
+}
+
+/// Waker given to the process future: it routes every wake up through the kernel scheduler.
+struct ProcessWaker {
+    shared: Arc<Shared>,
+    id: ProcessId,
+}
+
+impl ArcWake for ProcessWaker {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.shared.schedule(arc_self.id);
+    }
+}
+
+/// A simulation process.
+///
+/// The wrapped future is only polled when the kernel releases it during an evaluation phase, so
+/// that every process observes the same signal values for a given *delta-cycle*.
+pub(crate) struct Process {
+    id: ProcessId,
+    shared: Arc<Shared>,
+    future: Option<BoxFuture<'static, ()>>,
+    waker: Waker,
+}
+
+impl Process {
+    pub(crate) fn new(shared: Arc<Shared>, id: ProcessId, future: BoxFuture<'static, ()>) -> Self {
+        let waker = waker(Arc::new(ProcessWaker {
+            shared: shared.clone(),
+            id,
+        }));
+        Process {
+            id,
+            shared,
+            future: Some(future),
+            waker,
+        }
+    }
+
+    pub(crate) fn id(&self) -> ProcessId {
+        self.id
+    }
+}
+
+impl Future for Process {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        {
+            let mut state = self.shared.lock();
+            let process = &mut state.processes[self.id];
+            if !process.released {
+                process.waker = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+        }
+
+        let this = &mut *self;
+        let (finished, panic) = {
+            let _guard = context::enter(this.shared.clone(), this.id);
+            let mut process_cx = Context::from_waker(&this.waker);
+            let polled = catch_unwind(AssertUnwindSafe(|| match this.future.as_mut() {
+                Some(future) => future.as_mut().poll(&mut process_cx).is_ready(),
+                None => true,
+            }));
+            let (finished, panic) = match polled {
+                Ok(finished) => (finished, None),
+                Err(panic) => (true, Some(panic)),
+            };
+            if finished {
+                // Drop the process while its context is active, so that closing its signals is
+                // seen by the other processes in this evaluation phase.
+                this.future = None;
+            }
+            (finished, panic)
+        };
+
+        let mut state = this.shared.lock();
+        let process = &mut state.processes[this.id];
+        if finished {
+            process.finished = true;
+        } else if process.rewake {
+            process.rewake = false;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        } else {
+            process.waker = Some(cx.waker().clone());
+        }
+        process.released = false;
+        state.active -= 1;
+        if state.active == 0 {
+            this.shared.idle.notify_one();
+        }
+        drop(state);
+
+        if let Some(panic) = panic {
+            let name = this.shared.lock().processes[this.id].name.clone();
+            match name {
+                Some(name) => resume_unwind(Box::new(format!(
+                    "The process `{}` panicked: {}",
+                    name,
+                    panic_message(&*panic)
+                ))),
+                None => resume_unwind(panic),
+            }
+        }
+
+        if finished {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Returns the message of a panic payload.
+pub(crate) fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    match panic.downcast_ref::<String>() {
+        Some(message) => message,
+        None => panic
+            .downcast_ref::<&str>()
+            .copied()
+            .unwrap_or("Box<dyn Any>"),
+    }
+}
@@ -0,0 +1,92 @@
+//! This module holds the scheduler modes of the kernel.
+
+use super::process::ProcessId;
+
+/// How the kernel runs the processes during an evaluation phase.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SchedulerMode {
+    /// Every process is a tokio task, so the processes of a *delta-cycle* run in parallel on the
+    /// tokio runtime, in no particular order.
+    #[default]
+    Threaded,
+    /// Every process is polled by the kernel on the thread running the simulation.
+    ///
+    /// Without a seed, the processes of a *delta-cycle* run in the order they were spawned. With a
+    /// seed, this order is shuffled at every *delta-cycle*, which is useful to find races between
+    /// processes on purpose while keeping the runs reproducible.
+    Deterministic {
+        /// Seed of the shuffle.
+        seed: Option<u64>,
+    },
+}
+
+/// Order of evaluation of the runnable processes in [`SchedulerMode::Deterministic`].
+pub(crate) enum Order {
+    Spawn,
+    Shuffle(SplitMix64),
+}
+
+impl Order {
+    pub(crate) fn new(seed: Option<u64>) -> Self {
+        match seed {
+            None => Order::Spawn,
+            Some(seed) => Order::Shuffle(SplitMix64(seed)),
+        }
+    }
+
+    /// Put the runnable processes in evaluation order.
+    pub(crate) fn arrange(&mut self, runnable: &mut [ProcessId]) {
+        match self {
+            Order::Spawn => runnable.sort_unstable(),
+            Order::Shuffle(rng) => {
+                // Sort first so the shuffle does not depend on the wake up order.
+                runnable.sort_unstable();
+                for i in (1..runnable.len()).rev() {
+                    let j = (rng.next() % (i as u64 + 1)) as usize;
+                    runnable.swap(i, j);
+                }
+            }
+        }
+    }
+}
+
+/// Small pseudo-random generator, so that the shuffles do not depend on an external crate.
+pub(crate) struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spawn_order() {
+        let mut runnable = vec![3, 0, 2, 1];
+        Order::new(None).arrange(&mut runnable);
+        assert_eq!(vec![0, 1, 2, 3], runnable);
+    }
+
+    #[test]
+    fn test_shuffle_is_reproducible() {
+        let shuffle = |seed| {
+            let mut order = Order::new(Some(seed));
+            (0..4)
+                .map(|_| {
+                    let mut runnable: Vec<ProcessId> = (0..16).collect();
+                    order.arrange(&mut runnable);
+                    runnable
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(shuffle(42), shuffle(42));
+        assert_ne!(shuffle(42), shuffle(43));
+    }
+}
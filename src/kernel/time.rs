@@ -0,0 +1,158 @@
+//! This module holds the simulated time representation.
+
+use std::fmt;
+use std::ops::{Add, Sub};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// An absolute point in simulated time, measured from the start of the simulation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SimTime(Duration);
+
+impl SimTime {
+    /// The start of the simulation.
+    pub const ZERO: SimTime = SimTime(Duration::ZERO);
+
+    /// Constructs a simulated time from the elapsed duration since the start of the simulation.
+    pub const fn from_duration(since_start: Duration) -> Self {
+        SimTime(since_start)
+    }
+
+    /// Returns the duration elapsed since the start of the simulation.
+    pub const fn as_duration(&self) -> Duration {
+        self.0
+    }
+
+    /// Returns the simulated time in nanoseconds.
+    pub fn as_nanos(&self) -> u128 {
+        self.0.as_nanos()
+    }
+}
+
+impl Add<Duration> for SimTime {
+    type Output = SimTime;
+
+    fn add(self, rhs: Duration) -> Self::Output {
+        SimTime(self.0 + rhs)
+    }
+}
+
+impl Sub for SimTime {
+    type Output = Duration;
+
+    fn sub(self, rhs: SimTime) -> Self::Output {
+        self.0 - rhs.0
+    }
+}
+
+impl fmt::Display for SimTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ns", self.as_nanos())
+    }
+}
+
+/// Error returned when parsing a [`SimTime`] from a string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseTimeError {
+    /// The string does not start with an integer.
+    Number(String),
+    /// The unit is not one of `ns`, `us`, `ms` or `s`.
+    Unit(String),
+}
+
+impl fmt::Display for ParseTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseTimeError::Number(s) => write!(f, "'{}' does not start with an integer", s),
+            ParseTimeError::Unit(unit) => {
+                write!(f, "'{}' is not a time unit, expected ns, us, ms or s", unit)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseTimeError {}
+
+impl FromStr for SimTime {
+    type Err = ParseTimeError;
+
+    /// Parse a time from the start of the simulation, as in `"100 ns"` or `"5us"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let number: u64 = number
+            .parse()
+            .map_err(|_| ParseTimeError::Number(s.to_string()))?;
+        let duration = match unit.trim() {
+            "ns" => number.ns(),
+            "us" => number.us(),
+            "ms" => number.ms(),
+            "s" => number.s(),
+            unit => return Err(ParseTimeError::Unit(unit.to_string())),
+        };
+        Ok(SimTime(duration))
+    }
+}
+
+/// Shorthands to build a [`Duration`] from an integer, as in `10.ns()`.
+pub trait TimeExt {
+    /// Duration in nanoseconds.
+    fn ns(self) -> Duration;
+    /// Duration in microseconds.
+    fn us(self) -> Duration;
+    /// Duration in milliseconds.
+    fn ms(self) -> Duration;
+    /// Duration in seconds.
+    fn s(self) -> Duration;
+}
+
+impl TimeExt for u64 {
+    fn ns(self) -> Duration {
+        Duration::from_nanos(self)
+    }
+
+    fn us(self) -> Duration {
+        Duration::from_micros(self)
+    }
+
+    fn ms(self) -> Duration {
+        Duration::from_millis(self)
+    }
+
+    fn s(self) -> Duration {
+        Duration::from_secs(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_ext() {
+        assert_eq!(
+            SimTime::ZERO + 10.ns() + 2.us(),
+            SimTime::from_duration(2010.ns())
+        );
+    }
+
+    #[test]
+    fn test_time_display() {
+        assert_eq!("1500 ns", SimTime::from_duration(1500.ns()).to_string());
+    }
+
+    #[test]
+    fn test_time_parse() {
+        assert_eq!(Ok(SimTime::from_duration(100.ns())), "100ns".parse());
+        assert_eq!(Ok(SimTime::from_duration(5.us())), " 5 us".parse());
+        assert_eq!(
+            Err(ParseTimeError::Unit("ps".to_string())),
+            "3ps".parse::<SimTime>()
+        );
+        assert_eq!(
+            Err(ParseTimeError::Number("ns".to_string())),
+            "ns".parse::<SimTime>()
+        );
+    }
+}
@@ -0,0 +1,214 @@
+//! This module holds the timed waits on the kernel time wheel.
+
+use super::time::SimTime;
+use super::{context, Shared, State};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+/// When a [`Sleep`] should resume.
+enum Deadline {
+    After(Duration),
+    At(SimTime),
+}
+
+/// Time and delta-cycle at which a [`Sleep`] was registered on the time wheel.
+struct Armed {
+    shared: Arc<Shared>,
+    until: SimTime,
+    now: SimTime,
+    delta: u64,
+    waker: Waker,
+}
+
+impl Armed {
+    /// Returns true once the deadline is reached.
+    fn elapsed(&self, now: SimTime, delta: u64) -> bool {
+        if self.until > self.now {
+            now >= self.until
+        } else {
+            now > self.now || delta > self.delta
+        }
+    }
+
+    /// Register the waker on the time wheel, or for the next delta-cycle.
+    fn register(&self, state: &mut State) {
+        if self.until > self.now {
+            state
+                .timed
+                .entry(self.until)
+                .or_default()
+                .push(self.waker.clone());
+        } else {
+            state.delta_notifications.push(self.waker.clone());
+        }
+    }
+
+    /// Remove the waker registered by [`register`](Armed::register), if it did not wake yet.
+    fn cancel(&self, state: &mut State) {
+        if self.elapsed(state.now, state.delta) {
+            return;
+        }
+        let wakers = if self.until > self.now {
+            match state.timed.get_mut(&self.until) {
+                Some(wakers) => wakers,
+                None => return,
+            }
+        } else {
+            &mut state.delta_notifications
+        };
+        if let Some(index) = wakers.iter().position(|waker| waker.will_wake(&self.waker)) {
+            wakers.remove(index);
+        }
+        if self.until > self.now && wakers.is_empty() {
+            state.timed.remove(&self.until);
+        }
+    }
+}
+
+/// Future suspending the current process until a point in simulated time.
+///
+/// A deadline that is not in the future resumes the process at the next *delta-cycle*. Dropping
+/// the future before the deadline removes it from the time wheel, so that a timeout which lost a
+/// [`wait_any!`](crate::wait_any) does not extend the simulation.
+pub struct Sleep {
+    deadline: Deadline,
+    armed: Option<Armed>,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let kernel =
+            context::current().expect("Timed waits must be awaited in a simulation process.");
+        let mut state = kernel.shared.lock();
+        let (now, delta) = (state.now, state.delta);
+
+        match &mut self.armed {
+            None => {
+                let until = match self.deadline {
+                    Deadline::After(duration) => now + duration,
+                    Deadline::At(time) => time,
+                };
+                let armed = Armed {
+                    shared: kernel.shared.clone(),
+                    until,
+                    now,
+                    delta,
+                    waker: cx.waker().clone(),
+                };
+                armed.register(&mut state);
+                self.armed = Some(armed);
+                Poll::Pending
+            }
+            Some(armed) => {
+                if armed.elapsed(now, delta) {
+                    return Poll::Ready(());
+                }
+                if !armed.waker.will_wake(cx.waker()) {
+                    armed.cancel(&mut state);
+                    armed.waker = cx.waker().clone();
+                    armed.register(&mut state);
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if let Some(armed) = &self.armed {
+            // A poisoned state means that the kernel panicked, so there is nothing to clean up.
+            if let Ok(mut state) = armed.shared.state.lock() {
+                armed.cancel(&mut state);
+            }
+        }
+    }
+}
+
+/// Suspend the process for a simulated duration.
+///
+/// A zero duration resumes the process at the next *delta-cycle*.
+///
+/// # Panics
+/// The returned future panics if it is not awaited in a [`Simulator`](crate::Simulator) process.
+pub fn wait_for(duration: Duration) -> Sleep {
+    Sleep {
+        deadline: Deadline::After(duration),
+        armed: None,
+    }
+}
+
+/// Suspend the process until an absolute simulated time.
+///
+/// A time that is not in the future resumes the process at the next *delta-cycle*.
+///
+/// # Panics
+/// The returned future panics if it is not awaited in a [`Simulator`](crate::Simulator) process.
+pub fn wait_until(time: SimTime) -> Sleep {
+    Sleep {
+        deadline: Deadline::At(time),
+        armed: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel::now;
+    use crate::kernel::time::TimeExt;
+    use crate::Simulator;
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn test_wait_for() {
+        let mut sim = Simulator::new();
+        sim.spawn(async {
+            wait_for(10.ns()).await;
+            assert_eq!(SimTime::ZERO + 10.ns(), now());
+            wait_for(5.ns()).await;
+            assert_eq!(SimTime::ZERO + 15.ns(), now());
+        });
+        assert_eq!(SimTime::ZERO + 15.ns(), sim.run().await);
+    }
+
+    #[tokio::test]
+    async fn test_wait_until() {
+        let mut sim = Simulator::new();
+        sim.spawn(async {
+            wait_until(SimTime::ZERO + 1.us()).await;
+            assert_eq!(SimTime::ZERO + 1.us(), now());
+        });
+        assert_eq!(SimTime::ZERO + 1.us(), sim.run().await);
+    }
+
+    #[tokio::test]
+    async fn test_wait_zero_is_next_delta() {
+        let mut sim = Simulator::new();
+        sim.spawn(async {
+            wait_for(Duration::ZERO).await;
+            wait_until(SimTime::ZERO).await;
+        });
+        assert_eq!(SimTime::ZERO, sim.run().await);
+        assert_eq!(2, sim.delta_count());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_wait_order() {
+        let mut sim = Simulator::new();
+        let order = Arc::new(Mutex::new(Vec::new()));
+        for delay in [30, 10, 20] {
+            let order = order.clone();
+            sim.spawn(async move {
+                wait_for(delay.ns()).await;
+                order.lock().unwrap().push(now().as_nanos());
+            });
+        }
+        sim.run().await;
+        assert_eq!(vec![10, 20, 30], *order.lock().unwrap());
+    }
+}
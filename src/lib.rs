@@ -3,15 +3,42 @@
 //!
 //! This crate is inspired by SystemC, but does not follow it.
 
+// Lets the paths generated by the macros resolve inside this crate too.
+extern crate self as sand;
+
+pub mod cli;
+pub mod error;
+pub mod hierarchy;
+mod kernel;
+pub mod logic;
 pub mod ports;
 mod signals;
+pub mod synth;
+pub mod testbench;
+pub mod trace;
 use async_trait::async_trait;
+pub use error::Error;
+pub use kernel::scheduler::SchedulerMode;
+pub use kernel::time::{ParseTimeError, SimTime, TimeExt};
+pub use kernel::wait::{wait_for, wait_until, Sleep};
+pub use kernel::{now, spawn, stop, LagMode, Simulator, Statistics};
+pub use logic::{LogicVector, Signal, StdLogic};
+pub use sand_macros::{connections, entity, ports, test, Signal};
 pub use signals::buffer;
+pub use signals::clock;
+pub use signals::event;
 pub use signals::fifo;
+pub use signals::resolved;
 pub use signals::signal;
 
-/// Wait for a signal on the sensitivity list to trigger an event.
-async fn wait() -> Result<(), ()> {
+/// Suspend the process until the next *delta-cycle*.
+///
+/// This returns an error if it is not called from a [`Simulator`] process.
+pub async fn wait() -> Result<(), ()> {
+    if kernel::context::current().is_none() {
+        return Err(());
+    }
+    wait_for(std::time::Duration::ZERO).await;
     Ok(())
 }
 
@@ -24,7 +51,8 @@ pub trait Read<T> {
     /// - [`NBReadError::Empty`]
     /// - [`NBReadError::Closed`]
     ///
-    /// *This adds the read signal on the dynamic sensitivity list.*
+    /// *To run a process every time the signal changes, put the `changed()` event of the signal in
+    /// the static sensitivity of a [method](event::method) process.*
     fn nb_read(&mut self) -> Result<T, error::NBReadError>;
 
     /// Read the value currently on the signal.
@@ -46,29 +74,12 @@ pub trait Write<T> {
     /// Write a value to the signal.
     ///
     /// This returns immediately, but the signal is propagated at the next *delta-cycle*.
-    fn nb_write(&self, val: T);
-}
-
-/// Various simulation errors.
-pub mod error {
-    /// Non blocking read errors:
-    /// - [`NBReadError::Empty`]
-    /// - [`NBReadError::Closed`]
-    pub enum NBReadError {
-        /// When the signal has never been written to, it is in an undefined state.
-        Empty,
-        /// When all the signal [Sender]s are dropped, the signal becomes closed. This is useful to
-        /// end the simulation.
-        Closed,
-    }
-
-    /// Blocking read errors:
-    /// - [`BReadError::Closed`]
-    pub enum BReadError {
-        /// When all the signal [Sender]s are dropped, the signal becomes closed. This is useful to
-        /// end the simulation.
-        Closed,
-    }
+    /// Outside of a [`Simulator`] process, the signal is propagated immediately.
+    ///
+    /// The possible error value is:
+    /// - [`NBWriteError::Closed`](error::NBWriteError::Closed), when nothing can read the signal
+    ///   anymore.
+    fn nb_write(&self, val: T) -> Result<(), error::NBWriteError>;
 }
 
 #[cfg(test)]
@@ -0,0 +1,9 @@
+//! This module holds the logic types of the IEEE 1164 standard.
+
+mod packed;
+mod std_logic;
+mod vector;
+
+pub use packed::Signal;
+pub use std_logic::StdLogic;
+pub use vector::{LogicVector, ParseLogicError};
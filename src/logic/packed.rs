@@ -0,0 +1,218 @@
+//! This module holds the packing of values with a fixed bit layout in logic vectors.
+
+use super::{LogicVector, StdLogic};
+use crate::trace::Trace;
+
+/// A value type with a fixed bit layout, which can be packed in a [`LogicVector`] of
+/// [`WIDTH`](Trace::WIDTH) bits.
+///
+/// Structs of such values implement it with `#[derive(Signal)]`, which packs the fields as a
+/// SystemVerilog packed struct: the first field holds the most significant bits. The derived
+/// struct is traced with one VCD variable per field, named after the field.
+/// ```
+/// use sand::{LogicVector, Signal};
+///
+/// #[derive(Clone, PartialEq, Debug, Signal)]
+/// struct Header {
+///     valid: bool,
+///     address: u8,
+/// }
+///
+/// let header = Header { valid: true, address: 3 };
+/// let bits: LogicVector<9> = header.pack();
+/// assert_eq!("100000011", bits.to_string());
+/// assert_eq!(Some(header), Header::unpack(&bits));
+/// ```
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` has no fixed bit layout",
+    label = "this type cannot be packed in bits",
+    note = "derive `sand::Signal` on structs whose fields have a fixed size"
+)]
+pub trait Signal: Trace {
+    /// Write the `WIDTH` bits of the value to `bits`, least significant bit first.
+    fn pack_bits(&self, bits: &mut [StdLogic]);
+
+    /// Read a value from `WIDTH` bits, least significant bit first.
+    ///
+    /// Returns `None` if a bit is not a strong or weak `0` or `1`.
+    fn unpack_bits(bits: &[StdLogic]) -> Option<Self>;
+
+    /// Pack the value in a vector, whose width `N` must be `WIDTH`, which is checked at compile
+    /// time.
+    fn pack<const N: usize>(&self) -> LogicVector<N> {
+        const {
+            assert!(
+                N == Self::WIDTH,
+                "The vector width must be the width of the value."
+            )
+        };
+        let mut bits = [StdLogic::U; N];
+        self.pack_bits(&mut bits);
+        LogicVector::new(bits)
+    }
+
+    /// Unpack a value from a vector, whose width `N` must be `WIDTH`, which is checked at compile
+    /// time.
+    ///
+    /// Returns `None` if a bit is not a strong or weak `0` or `1`.
+    fn unpack<const N: usize>(vector: &LogicVector<N>) -> Option<Self> {
+        const {
+            assert!(
+                N == Self::WIDTH,
+                "The vector width must be the width of the value."
+            )
+        };
+        Self::unpack_bits(vector.bits())
+    }
+}
+
+impl Signal for bool {
+    fn pack_bits(&self, bits: &mut [StdLogic]) {
+        bits[0] = StdLogic::from(*self);
+    }
+
+    fn unpack_bits(bits: &[StdLogic]) -> Option<Self> {
+        bits[0].to_bool()
+    }
+}
+
+macro_rules! signal_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl Signal for $ty {
+                fn pack_bits(&self, bits: &mut [StdLogic]) {
+                    for (i, bit) in bits[..Self::WIDTH].iter_mut().enumerate() {
+                        *bit = StdLogic::from((self >> i) & 1 == 1);
+                    }
+                }
+
+                fn unpack_bits(bits: &[StdLogic]) -> Option<Self> {
+                    let mut value: $ty = 0;
+                    for (i, bit) in bits[..Self::WIDTH].iter().enumerate() {
+                        if bit.to_bool()? {
+                            value |= 1 << i;
+                        }
+                    }
+                    Some(value)
+                }
+            }
+        )*
+    };
+}
+
+signal_integer!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl Signal for StdLogic {
+    fn pack_bits(&self, bits: &mut [StdLogic]) {
+        bits[0] = *self;
+    }
+
+    /// A bit keeps its value, so unpacking never fails.
+    fn unpack_bits(bits: &[StdLogic]) -> Option<Self> {
+        Some(bits[0])
+    }
+}
+
+impl<const N: usize> Signal for LogicVector<N> {
+    fn pack_bits(&self, bits: &mut [StdLogic]) {
+        bits[..N].copy_from_slice(self.bits());
+    }
+
+    /// The bits keep their values, so unpacking never fails.
+    fn unpack_bits(bits: &[StdLogic]) -> Option<Self> {
+        let mut vector = [StdLogic::U; N];
+        vector.copy_from_slice(&bits[..N]);
+        Some(LogicVector::new(vector))
+    }
+}
+
+/// The element `0` holds the least significant bits, as a SystemVerilog packed array.
+impl<T: Signal, const N: usize> Signal for [T; N] {
+    fn pack_bits(&self, bits: &mut [StdLogic]) {
+        for (element, bits) in self.iter().zip(bits.chunks_mut(T::WIDTH)) {
+            element.pack_bits(bits);
+        }
+    }
+
+    fn unpack_bits(bits: &[StdLogic]) -> Option<Self> {
+        let elements = bits[..Self::WIDTH]
+            .chunks(T::WIDTH)
+            .map(T::unpack_bits)
+            .collect::<Option<Vec<T>>>()?;
+        elements.try_into().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Signal;
+
+    #[derive(Clone, Debug, PartialEq, Signal)]
+    struct Packet {
+        id: u8,
+        last: bool,
+        payload: [i8; 2],
+    }
+
+    #[derive(Clone, Debug, PartialEq, Signal)]
+    struct Wrapper(Packet, StdLogic);
+
+    #[test]
+    fn test_pack_integers() {
+        assert_eq!("00000101", 5u8.pack::<8>().to_string());
+        assert_eq!("11111110", (-2i8).pack::<8>().to_string());
+        assert_eq!(
+            Some(-2i8),
+            i8::unpack(&"11111110".parse::<LogicVector<8>>().unwrap())
+        );
+        assert_eq!(
+            None,
+            u8::unpack(&"1111111X".parse::<LogicVector<8>>().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_pack_struct() {
+        let packet = Packet {
+            id: 3,
+            last: true,
+            payload: [1, -1],
+        };
+        assert_eq!(25, Packet::WIDTH);
+        let bits: LogicVector<25> = packet.pack();
+        assert_eq!(
+            concat!("00000011", "1", "11111111", "00000001"),
+            bits.to_string()
+        );
+        assert_eq!(Some(packet.clone()), Packet::unpack(&bits));
+
+        let wrapper = Wrapper(packet, StdLogic::Z);
+        let bits: LogicVector<26> = wrapper.pack();
+        assert!(bits.to_string().ends_with('Z'));
+        assert_eq!(Some(wrapper), Wrapper::unpack(&bits));
+    }
+
+    #[test]
+    fn test_trace_struct() {
+        let mut vars = Vec::new();
+        Wrapper::trace_vars("top.bus", &mut vars);
+        assert_eq!(
+            vec![
+                ("top.bus.0.id".to_string(), 8),
+                ("top.bus.0.last".to_string(), 1),
+                ("top.bus.0.payload".to_string(), 16),
+                ("top.bus.1".to_string(), 1),
+            ],
+            vars
+        );
+        let mut bits = String::new();
+        Packet {
+            id: 1,
+            last: false,
+            payload: [0, 1],
+        }
+        .trace_bits(&mut bits);
+        assert_eq!(concat!("00000001", "0", "00000001", "00000000"), bits);
+    }
+}
@@ -0,0 +1,254 @@
+//! This module holds the nine-valued logic type of IEEE 1164.
+
+use std::fmt;
+use std::ops::{BitAnd, BitOr, BitXor, Not};
+
+/// A nine-valued logic bit, as the VHDL `std_logic` type.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum StdLogic {
+    /// `'U'`: uninitialized.
+    #[default]
+    U,
+    /// `'X'`: forcing unknown.
+    X,
+    /// `'0'`: forcing 0.
+    Zero,
+    /// `'1'`: forcing 1.
+    One,
+    /// `'Z'`: high impedance.
+    Z,
+    /// `'W'`: weak unknown.
+    W,
+    /// `'L'`: weak 0.
+    L,
+    /// `'H'`: weak 1.
+    H,
+    /// `'-'`: don't care.
+    DontCare,
+}
+
+use StdLogic::{DontCare as D, One as I, Zero as O, H, L, U, W, X, Z};
+
+/// Order of the values in the IEEE 1164 tables.
+const VALUES: [StdLogic; 9] = [U, X, O, I, Z, W, L, H, D];
+
+type Table = [[StdLogic; 9]; 9];
+
+const RESOLUTION_TABLE: Table = [
+    //U  X  0  1  Z  W  L  H  -
+    [U, U, U, U, U, U, U, U, U], // U
+    [U, X, X, X, X, X, X, X, X], // X
+    [U, X, O, X, O, O, O, O, X], // 0
+    [U, X, X, I, I, I, I, I, X], // 1
+    [U, X, O, I, Z, W, L, H, X], // Z
+    [U, X, O, I, W, W, W, W, X], // W
+    [U, X, O, I, L, W, L, W, X], // L
+    [U, X, O, I, H, W, W, H, X], // H
+    [U, X, X, X, X, X, X, X, X], // -
+];
+
+const AND_TABLE: Table = [
+    //U  X  0  1  Z  W  L  H  -
+    [U, U, O, U, U, U, O, U, U], // U
+    [U, X, O, X, X, X, O, X, X], // X
+    [O, O, O, O, O, O, O, O, O], // 0
+    [U, X, O, I, X, X, O, I, X], // 1
+    [U, X, O, X, X, X, O, X, X], // Z
+    [U, X, O, X, X, X, O, X, X], // W
+    [O, O, O, O, O, O, O, O, O], // L
+    [U, X, O, I, X, X, O, I, X], // H
+    [U, X, O, X, X, X, O, X, X], // -
+];
+
+const OR_TABLE: Table = [
+    //U  X  0  1  Z  W  L  H  -
+    [U, U, U, I, U, U, U, I, U], // U
+    [U, X, X, I, X, X, X, I, X], // X
+    [U, X, O, I, X, X, O, I, X], // 0
+    [I, I, I, I, I, I, I, I, I], // 1
+    [U, X, X, I, X, X, X, I, X], // Z
+    [U, X, X, I, X, X, X, I, X], // W
+    [U, X, O, I, X, X, O, I, X], // L
+    [I, I, I, I, I, I, I, I, I], // H
+    [U, X, X, I, X, X, X, I, X], // -
+];
+
+const XOR_TABLE: Table = [
+    //U  X  0  1  Z  W  L  H  -
+    [U, U, U, U, U, U, U, U, U], // U
+    [U, X, X, X, X, X, X, X, X], // X
+    [U, X, O, I, X, X, O, I, X], // 0
+    [U, X, I, O, X, X, I, O, X], // 1
+    [U, X, X, X, X, X, X, X, X], // Z
+    [U, X, X, X, X, X, X, X, X], // W
+    [U, X, O, I, X, X, O, I, X], // L
+    [U, X, I, O, X, X, I, O, X], // H
+    [U, X, X, X, X, X, X, X, X], // -
+];
+
+const NOT_TABLE: [StdLogic; 9] = [U, X, I, O, X, X, I, O, X];
+
+impl StdLogic {
+    /// Index of the value in the IEEE 1164 tables.
+    const fn index(self) -> usize {
+        match self {
+            U => 0,
+            X => 1,
+            O => 2,
+            I => 3,
+            Z => 4,
+            W => 5,
+            L => 6,
+            H => 7,
+            D => 8,
+        }
+    }
+
+    /// Resolve the values of two drivers of the same signal.
+    pub fn resolve(self, other: StdLogic) -> StdLogic {
+        RESOLUTION_TABLE[self.index()][other.index()]
+    }
+
+    /// Resolve the values of every driver of a signal.
+    ///
+    /// A signal without drivers is in high impedance.
+    pub fn resolve_all<Iter: IntoIterator<Item = StdLogic>>(drivers: Iter) -> StdLogic {
+        drivers.into_iter().fold(Z, StdLogic::resolve)
+    }
+
+    /// Returns true for the forcing values `'0'` and `'1'`.
+    pub fn is_strong(self) -> bool {
+        matches!(self, O | I)
+    }
+
+    /// Convert to the `'X'`, `'0'` and `'1'` subset, as the VHDL `to_x01` function.
+    pub fn to_x01(self) -> StdLogic {
+        match self {
+            O | L => O,
+            I | H => I,
+            _ => X,
+        }
+    }
+
+    /// Convert to a boolean if the value is a strong or weak `0` or `1`.
+    pub fn to_bool(self) -> Option<bool> {
+        match self.to_x01() {
+            O => Some(false),
+            I => Some(true),
+            _ => None,
+        }
+    }
+
+    /// Returns the character representing the value in VHDL.
+    pub fn to_char(self) -> char {
+        match self {
+            U => 'U',
+            X => 'X',
+            O => '0',
+            I => '1',
+            Z => 'Z',
+            W => 'W',
+            L => 'L',
+            H => 'H',
+            D => '-',
+        }
+    }
+
+    /// Parse the character representing the value in VHDL.
+    pub fn from_char(c: char) -> Option<StdLogic> {
+        VALUES
+            .iter()
+            .copied()
+            .find(|value| value.to_char() == c.to_ascii_uppercase())
+    }
+}
+
+impl From<bool> for StdLogic {
+    fn from(value: bool) -> Self {
+        if value {
+            I
+        } else {
+            O
+        }
+    }
+}
+
+impl fmt::Display for StdLogic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl BitAnd for StdLogic {
+    type Output = StdLogic;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        AND_TABLE[self.index()][rhs.index()]
+    }
+}
+
+impl BitOr for StdLogic {
+    type Output = StdLogic;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        OR_TABLE[self.index()][rhs.index()]
+    }
+}
+
+impl BitXor for StdLogic {
+    type Output = StdLogic;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        XOR_TABLE[self.index()][rhs.index()]
+    }
+}
+
+impl Not for StdLogic {
+    type Output = StdLogic;
+
+    fn not(self) -> Self::Output {
+        NOT_TABLE[self.index()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolution_is_commutative() {
+        for a in VALUES {
+            for b in VALUES {
+                assert_eq!(a.resolve(b), b.resolve(a));
+            }
+        }
+    }
+
+    #[test]
+    fn test_resolution() {
+        assert_eq!(O, O.resolve(Z));
+        assert_eq!(X, O.resolve(I));
+        assert_eq!(W, L.resolve(H));
+        assert_eq!(I, StdLogic::resolve_all([Z, H, I]));
+        assert_eq!(Z, StdLogic::resolve_all([]));
+    }
+
+    #[test]
+    fn test_operators() {
+        assert_eq!(O, O & U);
+        assert_eq!(I, H | X);
+        assert_eq!(I, I ^ L);
+        assert_eq!(X, I ^ Z);
+        assert_eq!(I, !L);
+        assert_eq!(U, !U);
+    }
+
+    #[test]
+    fn test_chars() {
+        for value in VALUES {
+            assert_eq!(Some(value), StdLogic::from_char(value.to_char()));
+        }
+        assert_eq!(None, StdLogic::from_char('2'));
+        assert_eq!("H", H.to_string());
+    }
+}
@@ -0,0 +1,338 @@
+//! This module holds the fixed-width vector of nine-valued logic bits.
+
+use super::StdLogic;
+use std::fmt;
+use std::ops::{BitAnd, BitOr, BitXor, Index, IndexMut, Not};
+use std::str::FromStr;
+
+/// A vector of `N` [`StdLogic`] bits, as the VHDL `std_logic_vector(N - 1 downto 0)` type.
+///
+/// Bit `0` is the least significant bit. The textual representation starts with the most
+/// significant bit, as in VHDL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LogicVector<const N: usize> {
+    bits: [StdLogic; N],
+}
+
+impl<const N: usize> LogicVector<N> {
+    /// Construct a vector from its bits, least significant bit first.
+    pub const fn new(bits: [StdLogic; N]) -> Self {
+        LogicVector { bits }
+    }
+
+    /// Construct a vector with every bit set to `value`.
+    pub const fn filled(value: StdLogic) -> Self {
+        LogicVector { bits: [value; N] }
+    }
+
+    /// Construct a vector from the `N` least significant bits of an unsigned integer.
+    pub fn from_uint(value: u128) -> Self {
+        let mut bits = [StdLogic::Zero; N];
+        for (i, bit) in bits.iter_mut().enumerate().take(128) {
+            *bit = StdLogic::from((value >> i) & 1 == 1);
+        }
+        LogicVector { bits }
+    }
+
+    /// Construct a vector from the `N` least significant bits of a signed integer.
+    pub fn from_int(value: i128) -> Self {
+        let mut bits = [StdLogic::from(value < 0); N];
+        for (i, bit) in bits.iter_mut().enumerate().take(128) {
+            *bit = StdLogic::from((value >> i) & 1 == 1);
+        }
+        LogicVector { bits }
+    }
+
+    /// Convert to an unsigned integer.
+    ///
+    /// Returns `None` if a bit is not a strong or weak `0` or `1`, or if the value does not fit.
+    pub fn to_uint(&self) -> Option<u128> {
+        let mut value = 0u128;
+        for (i, bit) in self.bits.iter().enumerate() {
+            let bit = bit.to_bool()?;
+            if bit {
+                if i >= 128 {
+                    return None;
+                }
+                value |= 1 << i;
+            }
+        }
+        Some(value)
+    }
+
+    /// Convert to a two's complement signed integer.
+    ///
+    /// Returns `None` if a bit is not a strong or weak `0` or `1`, or if the value does not fit.
+    pub fn to_int(&self) -> Option<i128> {
+        if N == 0 {
+            return Some(0);
+        }
+        let sign = self.bits[N - 1].to_bool()?;
+        let mut value = if sign { -1i128 } else { 0 };
+        for (i, bit) in self.bits.iter().enumerate() {
+            let bit = bit.to_bool()?;
+            if i >= 128 {
+                if bit != sign {
+                    return None;
+                }
+            } else if bit {
+                value |= 1 << i;
+            } else {
+                value &= !(1 << i);
+            }
+        }
+        Some(value)
+    }
+
+    /// Returns the bits, least significant bit first.
+    pub fn bits(&self) -> &[StdLogic; N] {
+        &self.bits
+    }
+
+    /// Returns the `M` bits starting at bit `low`, as the VHDL slice `(low + M - 1 downto low)`.
+    ///
+    /// # Panics
+    /// The slice must be within the vector.
+    pub fn slice<const M: usize>(&self, low: usize) -> LogicVector<M> {
+        assert!(
+            low + M <= N,
+            "Slice ({} downto {}) out of range for a vector of {} bits.",
+            low + M - 1,
+            low,
+            N
+        );
+        let mut bits = [StdLogic::U; M];
+        bits.copy_from_slice(&self.bits[low..low + M]);
+        LogicVector { bits }
+    }
+
+    /// Concatenate `self` as the most significant bits with `low`, as the VHDL `self & low`.
+    ///
+    /// The width `R` of the result must be `N + M`, which is checked at compile time.
+    pub fn concat<const M: usize, const R: usize>(&self, low: &LogicVector<M>) -> LogicVector<R> {
+        const {
+            assert!(
+                N + M == R,
+                "The concatenation width must be the sum of the widths."
+            )
+        };
+        let mut bits = [StdLogic::U; R];
+        bits[..M].copy_from_slice(&low.bits);
+        bits[M..].copy_from_slice(&self.bits);
+        LogicVector { bits }
+    }
+
+    /// Resolve the values of two drivers of the same signal, bit by bit.
+    pub fn resolve(&self, other: &LogicVector<N>) -> LogicVector<N> {
+        self.zip_with(other, StdLogic::resolve)
+    }
+
+    fn zip_with(&self, other: &Self, f: impl Fn(StdLogic, StdLogic) -> StdLogic) -> Self {
+        let mut bits = self.bits;
+        for (bit, other) in bits.iter_mut().zip(other.bits.iter()) {
+            *bit = f(*bit, *other);
+        }
+        LogicVector { bits }
+    }
+}
+
+impl<const N: usize> Default for LogicVector<N> {
+    /// Every bit is uninitialized.
+    fn default() -> Self {
+        Self::filled(StdLogic::U)
+    }
+}
+
+impl<const N: usize> From<u64> for LogicVector<N> {
+    fn from(value: u64) -> Self {
+        Self::from_uint(value as u128)
+    }
+}
+
+impl<const N: usize> From<u32> for LogicVector<N> {
+    fn from(value: u32) -> Self {
+        Self::from_uint(value as u128)
+    }
+}
+
+impl<const N: usize> From<[bool; N]> for LogicVector<N> {
+    fn from(bits: [bool; N]) -> Self {
+        LogicVector {
+            bits: bits.map(StdLogic::from),
+        }
+    }
+}
+
+impl<const N: usize> Index<usize> for LogicVector<N> {
+    type Output = StdLogic;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.bits[index]
+    }
+}
+
+impl<const N: usize> IndexMut<usize> for LogicVector<N> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.bits[index]
+    }
+}
+
+impl<const N: usize> fmt::Display for LogicVector<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.bits.iter().rev().try_for_each(|bit| bit.fmt(f))
+    }
+}
+
+/// Error returned when parsing a [`LogicVector`] from a string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseLogicError {
+    /// The string does not have exactly one character per bit.
+    Width {
+        /// Width of the vector.
+        expected: usize,
+        /// Number of characters in the string.
+        found: usize,
+    },
+    /// The character is not a [`StdLogic`] value.
+    Char(char),
+}
+
+impl fmt::Display for ParseLogicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseLogicError::Width { expected, found } => {
+                write!(f, "expected {} logic values, found {}", expected, found)
+            }
+            ParseLogicError::Char(c) => write!(f, "'{}' is not a logic value", c),
+        }
+    }
+}
+
+impl std::error::Error for ParseLogicError {}
+
+impl<const N: usize> FromStr for LogicVector<N> {
+    type Err = ParseLogicError;
+
+    /// Parse a vector, most significant bit first, as in `"01XZ"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let found = s.chars().count();
+        if found != N {
+            return Err(ParseLogicError::Width { expected: N, found });
+        }
+        let mut bits = [StdLogic::U; N];
+        for (bit, c) in bits.iter_mut().rev().zip(s.chars()) {
+            *bit = StdLogic::from_char(c).ok_or(ParseLogicError::Char(c))?;
+        }
+        Ok(LogicVector { bits })
+    }
+}
+
+impl<const N: usize> BitAnd for LogicVector<N> {
+    type Output = LogicVector<N>;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.zip_with(&rhs, BitAnd::bitand)
+    }
+}
+
+impl<const N: usize> BitOr for LogicVector<N> {
+    type Output = LogicVector<N>;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.zip_with(&rhs, BitOr::bitor)
+    }
+}
+
+impl<const N: usize> BitXor for LogicVector<N> {
+    type Output = LogicVector<N>;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        self.zip_with(&rhs, BitXor::bitxor)
+    }
+}
+
+impl<const N: usize> Not for LogicVector<N> {
+    type Output = LogicVector<N>;
+
+    fn not(self) -> Self::Output {
+        LogicVector {
+            bits: self.bits.map(Not::not),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signal::signal;
+    use crate::{Read, Write};
+
+    #[test]
+    fn test_uint_conversion() {
+        let vector = LogicVector::<8>::from_uint(0xa5);
+        assert_eq!("10100101", vector.to_string());
+        assert_eq!(Some(0xa5), vector.to_uint());
+        assert_eq!(Some(0x5), LogicVector::<4>::from(0x15u32).to_uint());
+        assert_eq!(None, LogicVector::<4>::default().to_uint());
+    }
+
+    #[test]
+    fn test_int_conversion() {
+        let vector = LogicVector::<8>::from_int(-3);
+        assert_eq!("11111101", vector.to_string());
+        assert_eq!(Some(-3), vector.to_int());
+        assert_eq!(Some(253), vector.to_uint());
+    }
+
+    #[test]
+    fn test_parse() {
+        let vector: LogicVector<4> = "1HZ-".parse().unwrap();
+        assert_eq!(StdLogic::DontCare, vector[0]);
+        assert_eq!(StdLogic::One, vector[3]);
+        assert_eq!(
+            Err(ParseLogicError::Width {
+                expected: 4,
+                found: 3
+            }),
+            "101".parse::<LogicVector<4>>()
+        );
+        assert_eq!(
+            Err(ParseLogicError::Char('2')),
+            "1012".parse::<LogicVector<4>>()
+        );
+    }
+
+    #[test]
+    fn test_slice_concat() {
+        let vector = LogicVector::<8>::from_uint(0xa5);
+        let high: LogicVector<4> = vector.slice(4);
+        let low: LogicVector<4> = vector.slice(0);
+        assert_eq!(Some(0xa), high.to_uint());
+        assert_eq!(Some(0x5), low.to_uint());
+        let swapped: LogicVector<8> = low.concat(&high);
+        assert_eq!(Some(0x5a), swapped.to_uint());
+        let restored: LogicVector<8> = high.concat(&low);
+        assert_eq!(vector, restored);
+    }
+
+    #[test]
+    fn test_operators() {
+        let a: LogicVector<4> = "01ZH".parse().unwrap();
+        let b: LogicVector<4> = "0110".parse().unwrap();
+        assert_eq!("0111", (a | b).to_string());
+        assert_eq!("01X0", (a & b).to_string());
+        assert_eq!("00X1", (a ^ b).to_string());
+        assert_eq!("10X0", (!a).to_string());
+        assert_eq!(
+            "0XZH".parse::<LogicVector<4>>().unwrap(),
+            a.resolve(&"0XZZ".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_signal_of_vector() {
+        let (tx, mut rx) = signal::<LogicVector<16>>();
+        tx.nb_write(LogicVector::from(0xbeefu32)).unwrap();
+        assert_eq!(Some(0xbeef), rx.nb_read().ok().and_then(|v| v.to_uint()));
+    }
+}
@@ -2,8 +2,8 @@ use std::collections::btree_map::BTreeMap;
 
 use proc_macro2::{Ident, TokenStream};
 use quote::{format_ident, quote, quote_spanned};
-use syn::Token;
-use syn::parse::{Parse, Parser, ParseStream};
+use syn::{Attribute, Token};
+use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
 use syn::token::Dot;
 
@@ -30,9 +30,86 @@ impl Parse for Connection {
     }
 }
 
+/// Connections, with whether the signals are traced.
+struct Connections {
+    trace: bool,
+    connections: Punctuated<Connection, Token![;]>,
+}
+
+impl Parse for Connections {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut trace = false;
+        for attr in input.call(Attribute::parse_inner)? {
+            if attr.path.is_ident("trace") && attr.tokens.is_empty() {
+                trace = true;
+            } else {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "The only attribute of the connections is `#![trace]`.",
+                ));
+            }
+        }
+        Ok(Connections {
+            trace,
+            connections: Punctuated::parse_terminated(input)?,
+        })
+    }
+}
+
+/// Name of the module instantiating the connected modules in the design hierarchy.
+const TOP: &str = "top";
+
+/// Expand the connections to a block evaluating to a `sand::Simulator` running every module.
+///
+/// Every signal is declared by the block. A signal connected to a `<->` port is a resolved
+/// signal, any other signal is a basic signal. The signals, ports and processes are registered
+/// in the design hierarchy under the `top` module, e.g. `top.module.port`, along with the
+/// connections of the ports, which are checked before the simulation starts. With `#![trace]`,
+/// every signal is traced under its path, so the values of the signals must implement
+/// `sand::trace::Trace`.
 pub fn connections(input: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
-    let parser = Punctuated::<Connection, Token![;]>::parse_terminated;
-    let connections = parser.parse2(input).unwrap();
+    let Connections { trace, connections } = match syn::parse2(input) {
+        Ok(connections) => connections,
+        Err(err) => return err.to_compile_error(),
+    };
+
+    // Signals in order of first appearance, with whether they are resolved.
+    let mut signals: Vec<(&Ident, bool)> = Vec::new();
+    for connection in connections.iter() {
+        let resolved = matches!(connection.way, Way::InOut);
+        match signals.iter_mut().find(|(signal, _)| *signal == &connection.signal) {
+            Some((_, is_resolved)) => *is_resolved |= resolved,
+            None => signals.push((&connection.signal, resolved)),
+        }
+    }
+    let is_resolved = |signal: &Ident| {
+        signals
+            .iter()
+            .any(|(other, resolved)| *other == signal && *resolved)
+    };
+
+    let declarations = signals.iter().map(|(signal, resolved)| {
+        let path = format!("{}.{}", TOP, signal);
+        let declaration = if *resolved {
+            quote_spanned! {signal.span()=>
+                let #signal = ::sand::resolved::named(#path).0;
+            }
+        } else {
+            quote_spanned! {signal.span()=>
+                let #signal = ::sand::signal::named(#path).0;
+            }
+        };
+        let traced = if trace {
+            quote!(simulator.trace(#path, &#signal);)
+        } else {
+            TokenStream::new()
+        };
+        quote!(
+            #declaration
+            simulator.register(#path, ::sand::hierarchy::Kind::Signal);
+            #traced
+        )
+    }).collect::<TokenStream>();
 
     let mut module_ports: BTreeMap<&Ident, TokenStream> = BTreeMap::new();
     let terminals = connections.iter().map(|connection| {
@@ -43,59 +120,71 @@ pub fn connections(input: proc_macro2::TokenStream) -> proc_macro2::TokenStream
             way,
             signal
         } = connection;
-        let box_ident = format_ident!("{}__{}__{}",  module, port, signal);
-
-        let (terminal, port_connection) = match way {
-            Way::In => (
-                quote_spanned! {signal.span()=>
-                    #signal.subscribe()
-                },
-                quote! {
-                    #port: system_rust::port::In {
-                        signal: #box_ident,
-                        value: None
-                    },
-                },
-            ),
-            Way::Out => (
-                quote_spanned! {signal.span()=>
-                    #signal.clone()
-                },
-                quote! {
-                    #port: system_rust::port::Out {
-                        signal: #box_ident
-                    },
-                },
-            )
+        let port_ident = format_ident!("{}__{}__{}",  module, port, signal);
+        let path = format!("{}.{}.{}", TOP, module, port);
+        let signal_path = format!("{}.{}", TOP, signal);
+        let direction = match way {
+            Way::In => quote!(In),
+            Way::Out => quote!(Out),
+            Way::InOut => quote!(InOut),
+        };
+
+        let terminal = match way {
+            Way::In if is_resolved(signal) => quote_spanned! {signal.span()=>
+                ::sand::ports::In::connect(::sand::resolved::Bus::subscribe(&#signal)).named(#path)
+            },
+            Way::In => quote_spanned! {signal.span()=>
+                ::sand::ports::In::connect(::sand::signal::Sender::subscribe(&#signal)).named(#path)
+            },
+            Way::Out if is_resolved(signal) => {
+                let message = format!(
+                    "The output port `{}.{}` cannot drive the resolved signal `{}`, connect it with `<->`.",
+                    module, port, signal
+                );
+                quote_spanned! {port.span()=>
+                    compile_error!(#message)
+                }
+            }
+            Way::Out => quote_spanned! {signal.span()=>
+                ::sand::ports::Out::connect(::std::clone::Clone::clone(&#signal)).named(#path)
+            },
+            Way::InOut => quote_spanned! {signal.span()=>
+                ::sand::ports::InOut::connect(&#signal, #path)
+            },
         };
 
         module_ports.entry(module)
             .or_default()
-            .extend(port_connection);
+            .extend(quote!(#port: #port_ident,));
 
         quote!(
-            let #box_ident = Box::new(#terminal);
+            #[allow(non_snake_case)]
+            let #port_ident = #terminal;
+            simulator.connect(#path, #signal_path, ::sand::hierarchy::Direction::#direction);
         )
     }).collect::<TokenStream>();
 
     let modules = module_ports.iter().map(|(module, port_connections)| {
         let module_ports_ident = format_ident!("{}_ports", module);
+        let path = format!("{}.{}.process", TOP, module);
         quote!(
-            tokio::task::spawn( async move {
+            simulator.spawn_named(#path, async move {
                 let mut #module_ports_ident = #module::Ports {
                     #port_connections
                 };
                 #module::process(&mut #module_ports_ident).await;
-            }),
+            });
         )
     }).collect::<TokenStream>();
 
     quote!(
-        #terminals
-        let children = vec![
+        {
+            let mut simulator = ::sand::Simulator::new();
+            #declarations
+            #terminals
             #modules
-        ];
-        join_all(children).await;
+            simulator
+        }
     )
 }
 
@@ -120,41 +209,130 @@ mod tests {
         file.write_all(format!("{}", generated).as_bytes()).unwrap();
 
         assert_eq!(generated.to_string(), quote!(
-            let mod1__first_out__mod1_to_mod2 = Box::new(mod1_to_mod2.clone());
+            {
+                let mut simulator = ::sand::Simulator::new();
+                let mod1_to_mod2 = ::sand::signal::named("top.mod1_to_mod2").0;
+                simulator.register("top.mod1_to_mod2", ::sand::hierarchy::Kind::Signal);
+
+                let mod2_to_mod1 = ::sand::signal::named("top.mod2_to_mod1").0;
+                simulator.register("top.mod2_to_mod1", ::sand::hierarchy::Kind::Signal);
+
+                #[allow(non_snake_case)]
+                let mod1__first_out__mod1_to_mod2 = ::sand::ports::Out::connect(::std::clone::Clone::clone(&mod1_to_mod2)).named("top.mod1.first_out");
+                simulator.connect("top.mod1.first_out", "top.mod1_to_mod2", ::sand::hierarchy::Direction::Out);
 
-            let mod2__second_out__mod2_to_mod1 = Box::new(mod2_to_mod1.clone());
+                #[allow(non_snake_case)]
+                let mod2__second_out__mod2_to_mod1 = ::sand::ports::Out::connect(::std::clone::Clone::clone(&mod2_to_mod1)).named("top.mod2.second_out");
+                simulator.connect("top.mod2.second_out", "top.mod2_to_mod1", ::sand::hierarchy::Direction::Out);
 
-            let mod1__third_in__mod2_to_mod1 = Box::new(mod2_to_mod1.subscribe());
+                #[allow(non_snake_case)]
+                let mod1__third_in__mod2_to_mod1 = ::sand::ports::In::connect(::sand::signal::Sender::subscribe(&mod2_to_mod1)).named("top.mod1.third_in");
+                simulator.connect("top.mod1.third_in", "top.mod2_to_mod1", ::sand::hierarchy::Direction::In);
 
-            let mod2__fourth_in__mod1_to_mod2 = Box::new(mod1_to_mod2.subscribe());
+                #[allow(non_snake_case)]
+                let mod2__fourth_in__mod1_to_mod2 = ::sand::ports::In::connect(::sand::signal::Sender::subscribe(&mod1_to_mod2)).named("top.mod2.fourth_in");
+                simulator.connect("top.mod2.fourth_in", "top.mod1_to_mod2", ::sand::hierarchy::Direction::In);
 
-            let children = vec![
-                tokio::task::spawn( async move {
+                simulator.spawn_named("top.mod1.process", async move {
                     let mut mod1_ports = mod1::Ports {
-                        first_out: system_rust::ports::Out {
-                            signal: mod1__first_out__mod1_to_mod2
-                        },
-                        third_in: system_rust::ports::In {
-                            signal: mod1__third_in__mod2_to_mod1,
-                            value: None
-                        },
+                        first_out: mod1__first_out__mod1_to_mod2,
+                        third_in: mod1__third_in__mod2_to_mod1,
                     };
                     mod1::process(&mut mod1_ports).await;
-                }),
-                tokio::task::spawn( async move {
+                });
+                simulator.spawn_named("top.mod2.process", async move {
                     let mut mod2_ports = mod2::Ports {
-                        second_out: system_rust::ports::Out {
-                            signal: mod2__second_out__mod2_to_mod1
-                        },
-                        fourth_in: system_rust::ports::In {
-                            signal: mod2__fourth_in__mod1_to_mod2,
-                            value: None
-                        },
+                        second_out: mod2__second_out__mod2_to_mod1,
+                        fourth_in: mod2__fourth_in__mod1_to_mod2,
                     };
                     mod2::process(&mut mod2_ports).await;
-                }),
-            ];
-            join_all(children).await;
+                });
+                simulator
+            }
         ).to_string());
     }
+
+    #[test]
+    fn connections_inout_test() {
+        let generated = connections(quote!(
+            master.sda <-> sda;
+            slave.sda <-> sda;
+            monitor.sda <- sda;
+        ));
+
+        assert_eq!(generated.to_string(), quote!(
+            {
+                let mut simulator = ::sand::Simulator::new();
+                let sda = ::sand::resolved::named("top.sda").0;
+                simulator.register("top.sda", ::sand::hierarchy::Kind::Signal);
+
+                #[allow(non_snake_case)]
+                let master__sda__sda = ::sand::ports::InOut::connect(&sda, "top.master.sda");
+                simulator.connect("top.master.sda", "top.sda", ::sand::hierarchy::Direction::InOut);
+
+                #[allow(non_snake_case)]
+                let slave__sda__sda = ::sand::ports::InOut::connect(&sda, "top.slave.sda");
+                simulator.connect("top.slave.sda", "top.sda", ::sand::hierarchy::Direction::InOut);
+
+                #[allow(non_snake_case)]
+                let monitor__sda__sda = ::sand::ports::In::connect(::sand::resolved::Bus::subscribe(&sda)).named("top.monitor.sda");
+                simulator.connect("top.monitor.sda", "top.sda", ::sand::hierarchy::Direction::In);
+
+                simulator.spawn_named("top.master.process", async move {
+                    let mut master_ports = master::Ports {
+                        sda: master__sda__sda,
+                    };
+                    master::process(&mut master_ports).await;
+                });
+                simulator.spawn_named("top.monitor.process", async move {
+                    let mut monitor_ports = monitor::Ports {
+                        sda: monitor__sda__sda,
+                    };
+                    monitor::process(&mut monitor_ports).await;
+                });
+                simulator.spawn_named("top.slave.process", async move {
+                    let mut slave_ports = slave::Ports {
+                        sda: slave__sda__sda,
+                    };
+                    slave::process(&mut slave_ports).await;
+                });
+                simulator
+            }
+        ).to_string());
+    }
+
+    #[test]
+    fn connections_trace_test() {
+        let generated = connections(quote!(
+            #![trace]
+            counter.count -> count;
+        ));
+
+        assert_eq!(generated.to_string(), quote!(
+            {
+                let mut simulator = ::sand::Simulator::new();
+                let count = ::sand::signal::named("top.count").0;
+                simulator.register("top.count", ::sand::hierarchy::Kind::Signal);
+                simulator.trace("top.count", &count);
+
+                #[allow(non_snake_case)]
+                let counter__count__count = ::sand::ports::Out::connect(::std::clone::Clone::clone(&count)).named("top.counter.count");
+                simulator.connect("top.counter.count", "top.count", ::sand::hierarchy::Direction::Out);
+
+                simulator.spawn_named("top.counter.process", async move {
+                    let mut counter_ports = counter::Ports {
+                        count: counter__count__count,
+                    };
+                    counter::process(&mut counter_ports).await;
+                });
+                simulator
+            }
+        ).to_string());
+
+        let generated = connections(quote!(
+            #![dump]
+            counter.count -> count;
+        ));
+        assert!(generated.to_string().contains("The only attribute of the connections is `#![trace]`."));
+    }
 }
@@ -1,21 +1,27 @@
 use std::collections::HashSet;
-use proc_macro2::{Ident, TokenStream};
-use quote::{quote, quote_spanned};
+use proc_macro2::{Ident, TokenStream, TokenTree};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
 use syn::visit::{self, Visit};
 use syn::parse::{Parse, ParseStream, Result};
 use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
-use syn::{ItemFn, Receiver, ReturnType, Token, Type, TypeTuple, UnOp};
+use syn::{
+    ExprAsync, ExprCall, ExprClosure, ExprLoop, ExprMethodCall, ExprPath, ExprWhile, FnArg, ItemFn,
+    Lit, Pat, PatIdent, Receiver, ReturnType, Signature, Token, Type, TypeReference, TypeTuple, UnOp,
+};
 
+use crate::lower::lower;
+
+/// Arguments of the `#[entity]` attribute, which are the names of the clock inputs.
 struct Args {
-    vars: HashSet<Ident>,
+    clocks: HashSet<Ident>,
 }
 
 impl Parse for Args {
     fn parse(input: ParseStream) -> Result<Self> {
-        let vars = Punctuated::<Ident, Token![,]>::parse_terminated(input)?;
+        let clocks = Punctuated::<Ident, Token![,]>::parse_terminated(input)?;
         Ok(Args {
-            vars: vars.into_iter().collect(),
+            clocks: clocks.into_iter().collect(),
         })
     }
 }
@@ -25,14 +31,33 @@ impl Parse for Args {
 struct EntityVisitor {
     errors: TokenStream,
     type_assertions: TokenStream,
+    /// Assertions on types using `Self`, which cannot be named in the `_AssertCopy` item.
+    self_assertions: TokenStream,
+    /// Name of the entity function, to find recursive calls.
+    entity: Option<Ident>,
+    /// Names bound so far by the arguments and `let` statements.
+    bindings: HashSet<Ident>,
 }
 
 impl<'ast> Visit<'ast> for EntityVisitor {
     /// Assert that self is copy
     fn visit_receiver(&mut self, rec: &'ast Receiver) {
-        self.type_assertions.extend(
-            quote_spanned! {rec.span()=> Self: std::marker::Copy,}
-        )
+        self.self_assertions.extend(
+            quote_spanned! {rec.span()=> _assert_copy::<Self>();}
+        );
+        self.bindings.insert(rec.self_token.into());
+        visit::visit_receiver(self, rec);
+    }
+
+    /// Make async functions illegal
+    fn visit_signature(&mut self, sig: &'ast Signature) {
+        if let Some(asyncness) = sig.asyncness {
+            self.errors.extend(quote_spanned! {
+                asyncness.span()=>
+                compile_error!("Async functions are not synthesizable, hardware has no executor to poll them.");
+            });
+        }
+        visit::visit_signature(self, sig);
     }
 
     /// Check that every function has a return type
@@ -64,7 +89,18 @@ impl<'ast> Visit<'ast> for EntityVisitor {
 
     /// Find all types used to assert Copy on them
     fn visit_type(&mut self, ty: &'ast Type) {
-        self.type_assertions.extend(quote_spanned! {ty.span()=> #ty: std::marker::Copy,});
+        if let Some(message) = rejected_type(ty) {
+            // The type is not asserted Copy, nor visited further, so that it is reported once.
+            self.errors.extend(quote_spanned! {ty.span()=> compile_error!(#message);});
+            return;
+        }
+        if let Type::Reference(TypeReference { mutability: None, .. }) = ty {
+            // Shared references are always Copy, and cannot be named without their lifetime.
+        } else if uses_self(ty.to_token_stream()) {
+            self.self_assertions.extend(quote_spanned! {ty.span()=> _assert_copy::<#ty>();});
+        } else {
+            self.type_assertions.extend(quote_spanned! {ty.span()=> #ty: std::marker::Copy,});
+        }
         visit::visit_type(self, ty);
     }
 
@@ -78,26 +114,245 @@ impl<'ast> Visit<'ast> for EntityVisitor {
         }
         visit::visit_un_op(self, node);
     }
+
+    /// Make floating-point literals illegal
+    fn visit_lit(&mut self, lit: &'ast Lit) {
+        if let Lit::Float(_) = lit {
+            self.errors.extend(quote_spanned! {
+                lit.span()=>
+                compile_error!("Floating-point numbers are not synthesizable, please use fixed-point integers.");
+            });
+        }
+        visit::visit_lit(self, lit);
+    }
+
+    /// Make unbounded loops illegal
+    fn visit_expr_loop(&mut self, node: &'ast ExprLoop) {
+        self.errors.extend(quote_spanned! {
+            node.loop_token.span()=>
+            compile_error!("Unbounded loops are not synthesizable, please iterate over a constant range with `for`.");
+        });
+        visit::visit_expr_loop(self, node);
+    }
+
+    /// Make while loops illegal, as their number of iterations is unbounded
+    fn visit_expr_while(&mut self, node: &'ast ExprWhile) {
+        self.errors.extend(quote_spanned! {
+            node.while_token.span()=>
+            compile_error!("While loops are not synthesizable, please iterate over a constant range with `for`.");
+        });
+        visit::visit_expr_while(self, node);
+    }
+
+    /// Make async blocks illegal
+    fn visit_expr_async(&mut self, node: &'ast ExprAsync) {
+        self.errors.extend(quote_spanned! {
+            node.async_token.span()=>
+            compile_error!("Async blocks are not synthesizable, hardware has no executor to poll them.");
+        });
+        visit::visit_expr_async(self, node);
+    }
+
+    /// Make recursive calls illegal
+    fn visit_expr_call(&mut self, node: &'ast ExprCall) {
+        if let syn::Expr::Path(ExprPath { path, qself: None, .. }) = &*node.func {
+            let segments: Vec<&Ident> = path.segments.iter().map(|segment| &segment.ident).collect();
+            let recursive = match segments.as_slice() {
+                [name] | [_, name] if Some(*name) == self.entity.as_ref() => {
+                    segments.len() == 1 || segments[0] == "Self"
+                }
+                _ => false,
+            };
+            if recursive {
+                self.recursion(node.func.span());
+            }
+        }
+        visit::visit_expr_call(self, node);
+    }
+
+    /// Make recursive method calls illegal
+    fn visit_expr_method_call(&mut self, node: &'ast ExprMethodCall) {
+        let on_self = matches!(&*node.receiver, syn::Expr::Path(ExprPath { path, .. }) if path.is_ident("self"));
+        if on_self && Some(&node.method) == self.entity.as_ref() {
+            self.recursion(node.method.span());
+        }
+        visit::visit_expr_method_call(self, node);
+    }
+
+    /// Make closures capturing bindings by reference illegal
+    fn visit_expr_closure(&mut self, node: &'ast ExprClosure) {
+        if node.capture.is_none() {
+            let mut idents = Idents::default();
+            idents.visit_expr_closure(node);
+            let captured = idents.paths.iter().find(|ident| {
+                self.bindings.contains(*ident) && !idents.bindings.contains(*ident)
+            });
+            if let Some(captured) = captured {
+                let message = format!(
+                    "This closure captures `{}` by reference, which is not synthesizable, please make it a `move` closure.",
+                    captured
+                );
+                self.errors.extend(quote_spanned! {captured.span()=> compile_error!(#message);});
+            }
+        }
+        visit::visit_expr_closure(self, node);
+    }
+
+    /// Find all bindings in scope of the closures
+    fn visit_pat_ident(&mut self, node: &'ast PatIdent) {
+        self.bindings.insert(node.ident.clone());
+        visit::visit_pat_ident(self, node);
+    }
+}
+
+impl EntityVisitor {
+    fn recursion(&mut self, span: proc_macro2::Span) {
+        self.errors.extend(quote_spanned! {
+            span=>
+            compile_error!("Recursive calls are not synthesizable, please unroll the recursion.");
+        });
+    }
+}
+
+/// Returns the diagnostic of a type that cannot be synthesized.
+fn rejected_type(ty: &Type) -> Option<&'static str> {
+    match ty {
+        Type::Path(path) => {
+            let ident = &path.path.segments.last()?.ident;
+            if ident == "Vec" || ident == "Box" || ident == "String" {
+                Some("Heap allocated types are not synthesizable, please use fixed size arrays and values.")
+            } else if ident == "f32" || ident == "f64" {
+                Some("Floating-point numbers are not synthesizable, please use fixed-point integers.")
+            } else {
+                None
+            }
+        }
+        Type::TraitObject(_) => Some("Trait objects are not synthesizable, please use generics."),
+        Type::Ptr(_) => Some("Raw pointers are not synthesizable, please refactor your algorithm semantics to work with values."),
+        _ => None,
+    }
+}
+
+/// AST visitor that finds the bindings used and declared in a closure.
+#[derive(Default)]
+struct Idents {
+    paths: Vec<Ident>,
+    bindings: HashSet<Ident>,
+}
+
+impl<'ast> Visit<'ast> for Idents {
+    fn visit_expr_path(&mut self, node: &'ast ExprPath) {
+        if let Some(ident) = node.path.get_ident() {
+            self.paths.push(ident.clone());
+        }
+        visit::visit_expr_path(self, node);
+    }
+
+    fn visit_pat_ident(&mut self, node: &'ast PatIdent) {
+        self.bindings.insert(node.ident.clone());
+        visit::visit_pat_ident(self, node);
+    }
+}
+
+/// Returns true if the tokens name the `Self` type.
+fn uses_self(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => ident == "Self",
+        TokenTree::Group(group) => uses_self(group.stream()),
+        _ => false,
+    })
 }
 
 pub fn entity(args: TokenStream, input: TokenStream) -> TokenStream {
-    let entity_fn: ItemFn = syn::parse2(input).unwrap();
+    let entity_fn: ItemFn = match syn::parse2(input) {
+        Ok(entity_fn) => entity_fn,
+        Err(err) => return err.to_compile_error(),
+    };
+    let Args { clocks } = match syn::parse2(args) {
+        Ok(args) => args,
+        Err(err) => return err.to_compile_error(),
+    };
 
-    let mut visitor = EntityVisitor::default();
+    let mut visitor = EntityVisitor {
+        entity: Some(entity_fn.sig.ident.clone()),
+        ..Default::default()
+    };
     visitor.visit_item_fn(&entity_fn);
 
-    let EntityVisitor { errors, type_assertions } = visitor;
+    let EntityVisitor { mut errors, type_assertions, self_assertions, .. } = visitor;
+
+    // The clocks are only read by the registers of the synthesized entity.
+    let mut clock_uses = TokenStream::new();
+    let mut clock_names: Vec<&Ident> = clocks.iter().collect();
+    clock_names.sort();
+    for clock in clock_names {
+        let is_arg = entity_fn.sig.inputs.iter().any(|arg| match arg {
+            FnArg::Typed(arg) => matches!(&*arg.pat, Pat::Ident(pat) if pat.ident == *clock),
+            FnArg::Receiver(_) => false,
+        });
+        if is_arg {
+            clock_uses.extend(quote! { let _ = #clock; });
+        } else {
+            let message = format!("The clock `{}` is not an argument of the entity.", clock);
+            errors.extend(quote_spanned! {clock.span()=> compile_error!(#message);});
+        }
+    }
+
+    // Methods are checked but not lowered, as they are not units of their own.
+    let is_method = matches!(entity_fn.sig.inputs.first(), Some(FnArg::Receiver(_)));
+    let mut synth = TokenStream::new();
+    let mut warnings = TokenStream::new();
+    if errors.is_empty() && !is_method {
+        let name = entity_fn.sig.ident.to_string();
+        match lower(&entity_fn, &clocks) {
+            Ok(module) => {
+                let vis = &entity_fn.vis;
+                let constant = format_ident!("{}_ENTITY", name.to_uppercase());
+                let assembly = llhd::assembly::write_module_string(&module);
+                synth.extend(quote! {
+                    /// The LLHD IR of the entity.
+                    #vis const #constant: ::sand::synth::Entity = ::sand::synth::Entity {
+                        name: #name,
+                        assembly: #assembly,
+                    };
+                });
+            }
+            // The entity can still be simulated, so this is only a warning, raised by the use of a
+            // deprecated item as there are no warnings for procedural macros on stable.
+            Err(err) => {
+                let message = format!("{} No LLHD entity is generated for `{}`.", err, name);
+                warnings.extend(quote_spanned! {err.span()=>
+                    #[deprecated(note = #message)]
+                    struct NotSynthesized;
+                    let _ = NotSynthesized;
+                });
+            }
+        }
+    }
+    let self_assertions = if self_assertions.is_empty() {
+        self_assertions
+    } else {
+        quote! {
+            fn _assert_copy<T: std::marker::Copy>() {}
+            #self_assertions
+        }
+    };
 
     let ItemFn { attrs, vis, sig, block } = entity_fn;
 
     quote! {
+        #(#attrs)*
         #vis #sig {
             {
                 #errors
+                #warnings
                 struct _AssertCopy where #type_assertions;
+                #self_assertions
             }
+            #clock_uses
             #block
         }
+        #synth
     }
 }
 
@@ -122,6 +377,11 @@ mod tests {
             }
                 { a + b }
             }
+            /// The LLHD IR of the entity.
+            const ADDER_ENTITY: ::sand::synth::Entity = ::sand::synth::Entity {
+                name: "adder",
+                assembly: "entity @adder (i32$ %a, i32$ %b) -> (i32$ %out) {\n    %0 = prb i32$ %a\n    %1 = prb i32$ %b\n    %2 = add i32 %0, %1\n    %3 = const time 0s 1d\n    drv i32$ %out, %2, %3\n}\n",
+            };
         ).to_string());
         // let mut file = File::create("test_entity.rs").unwrap();
         // file.write_all(format!("{}", generated).as_bytes()).unwrap();
@@ -139,6 +399,9 @@ mod tests {
         assert_eq!(generated.to_string(), quote!(
             fn const_adder(a: i32) -> i32 {
                 {
+                    #[deprecated(note = "The operands must have the same type to be lowered to LLHD. No LLHD entity is generated for `const_adder`.")]
+                    struct NotSynthesized;
+                    let _ = NotSynthesized;
                     struct _AssertCopy
                         where i32: std::marker::Copy, i32: std::marker::Copy, u8: std::marker::Copy,;
                 }
@@ -221,8 +484,10 @@ mod tests {
         assert_eq!(generated.to_string(), quote!(
             fn increment(&self) -> Self {
                 {
-                    struct _AssertCopy
-                        where Self: std::marker::Copy, Self: std::marker::Copy,;
+                    struct _AssertCopy where;
+                    fn _assert_copy<T: std::marker::Copy>() {}
+                    _assert_copy::<Self>();
+                    _assert_copy::<Self>();
                 }
                 {
                     self.c + self.step
@@ -232,4 +497,25 @@ mod tests {
         // let mut file = File::create("test_entity.rs").unwrap();
         // file.write_all(format!("{}", generated).as_bytes()).unwrap();
     }
+
+    #[test]
+    fn entity_should_error_for_unbounded_loop() {
+        let generated = entity(TokenStream::default(), quote! {
+            fn spin(a: u8) -> u8 {
+                loop {}
+            }
+        });
+        assert_eq!(generated.to_string(), quote!(
+            fn spin(a: u8) -> u8 {
+                {
+                    compile_error!("Unbounded loops are not synthesizable, please iterate over a constant range with `for`.");
+                    struct _AssertCopy
+                        where u8: std::marker::Copy, u8: std::marker::Copy,;
+                }
+                {
+                    loop {}
+                }
+            }
+        ).to_string());
+    }
 }
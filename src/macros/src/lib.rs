@@ -2,13 +2,77 @@ mod ports;
 mod connections;
 mod way;
 mod entity;
+mod lower;
+mod signal;
+mod testbench;
 
+/// Declare the `Ports` struct of a module.
+///
+/// Every port is declared as `name <- Type` for an input, `name -> Type` for an output or
+/// `name <-> Type` for a bidirectional port on a resolved signal.
 #[proc_macro]
 pub fn ports(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     ports::ports(input.into()).into()
 }
 
+/// Connect the ports of modules with signals, and spawn the modules in a new `Simulator`.
+///
+/// Every connection is declared as `module.port <- signal`, `module.port -> signal` or
+/// `module.port <-> signal`, with the same arrow as the port declaration. The signals are
+/// declared by the macro, and a signal connected to a `<->` port is a resolved signal. Every
+/// module must hold a `Ports` struct and a `process(&mut Ports)` async function.
+///
+/// The signals, ports and processes are registered in the design hierarchy of the simulator under
+/// the `top` module, as `top.signal`, `top.module.port` and `top.module.process`. The connections
+/// are registered too, so that the dangling inputs, the outputs without a reader and the signals
+/// with several drivers are reported before the simulation starts.
+///
+/// Starting with `#![trace]`, every signal is traced as `top.signal` in the waveform dumped by
+/// `Simulator::dump_vcd`, so the values of the signals must implement `sand::trace::Trace`, e.g.
+/// by deriving `Signal`.
+///
+/// This evaluates to the `Simulator`, ready to be run.
 #[proc_macro]
 pub fn connections(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     connections::connections(input.into()).into()
 }
+
+/// Check that a function is synthesizable, and lower it to an LLHD entity.
+///
+/// The function still compiles to Rust to be simulated. Its arguments are the inputs of the
+/// entity and its return value the outputs, and every type it uses must be `Copy`. Unless it is
+/// a method, the LLHD IR is stored in a `sand::synth::Entity` constant named after the function,
+/// e.g. `ADDER_ENTITY` for `fn adder`. A function using a construct that cannot be lowered yet,
+/// such as a method call, is only simulated: it compiles with a warning and has no constant.
+///
+/// The arguments of the attribute name the clock inputs of the entity, which must be `bool`
+/// arguments. Without clocks the entity is combinational and its outputs are driven after a
+/// *delta-cycle*, while `#[entity(clk)]` stores the outputs in registers on the rising edge of
+/// `clk`.
+#[proc_macro_attribute]
+pub fn entity(args: proc_macro::TokenStream, input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    entity::entity(args.into(), input.into()).into()
+}
+
+/// Derive `sand::Signal` for a struct whose fields have a fixed bit layout.
+///
+/// The fields are packed as a SystemVerilog packed struct, the first field holding the most
+/// significant bits. The struct is traced with one variable per field, named after the field.
+/// A field without a fixed size, such as a `Vec`, is rejected: use an array instead.
+#[proc_macro_derive(Signal)]
+pub fn signal(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    signal::derive_signal(input.into()).into()
+}
+
+/// Run an `async fn` without arguments as a test, in a process of a new `Simulator`.
+///
+/// The simulation runs with the deterministic scheduler on a tokio runtime until the testbench
+/// returns, or until the time limit given as `#[sand::test(until = "1 us")]`, which is checked at
+/// compile time. A panic in the testbench or
+/// in any process it spawns with `sand::spawn` fails the test, and so does a testbench which did
+/// not return before the end of the simulation. The message gives the simulated time at which the
+/// test failed.
+#[proc_macro_attribute]
+pub fn test(args: proc_macro::TokenStream, input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    testbench::test(args.into(), input.into()).into()
+}
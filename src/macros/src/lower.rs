@@ -0,0 +1,953 @@
+use std::collections::{HashMap, HashSet};
+
+use llhd::ir::{
+    Module, RegMode, RegTrigger, Signature, UnitBuilder, UnitData, UnitKind, UnitName, Value,
+};
+use llhd::verifier::Verifier;
+use llhd::{int_ty, signal_ty, IntValue, TimeValue};
+use proc_macro2::Ident;
+use syn::spanned::Spanned;
+use syn::{
+    BinOp, Block, Error, Expr, ExprForLoop, FnArg, ItemFn, Lit, Pat, RangeLimits, ReturnType, Stmt,
+    Type, UnOp,
+};
+
+/// Integer type of a value, `bool` being an unsigned integer of one bit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Ty {
+    width: usize,
+    signed: bool,
+}
+
+impl Ty {
+    const BOOL: Ty = Ty {
+        width: 1,
+        signed: false,
+    };
+
+    fn parse(ty: &Type) -> syn::Result<Ty> {
+        let name = match ty {
+            Type::Path(path) if path.qself.is_none() => {
+                path.path.get_ident().map(|id| id.to_string())
+            }
+            Type::Paren(paren) => return Ty::parse(&paren.elem),
+            Type::Group(group) => return Ty::parse(&group.elem),
+            _ => None,
+        };
+        name.as_deref().and_then(Ty::from_name).ok_or_else(|| {
+            Error::new(
+                ty.span(),
+                "Only booleans and fixed width integers can be lowered to LLHD.",
+            )
+        })
+    }
+
+    fn from_name(name: &str) -> Option<Ty> {
+        let (signed, width) = match name {
+            "bool" => return Some(Ty::BOOL),
+            _ if name.starts_with('u') => (false, &name[1..]),
+            _ if name.starts_with('i') => (true, &name[1..]),
+            _ => return None,
+        };
+        match width {
+            "8" | "16" | "32" | "64" | "128" => Some(Ty {
+                width: width.parse().ok()?,
+                signed,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Maximum number of iterations of an unrolled `for` loop.
+const MAX_ITERATIONS: usize = 1024;
+
+/// Binding in scope, which is a lowered value or the index of an unrolled `for` loop.
+#[derive(Clone, Copy)]
+enum Binding {
+    Value(Value, Ty),
+    Index(usize),
+}
+
+/// Bindings in scope.
+type Scope = HashMap<String, Binding>;
+
+/// Lower an entity function to an LLHD module holding one entity unit.
+///
+/// Every argument is an input signal and the return value drives the `out` output signal, or the
+/// `out0`, `out1`... output signals when it is a tuple. The body is lowered to pure data flow, so
+/// both branches of an `if` or every arm of a `match` are evaluated and multiplexed, and the `for`
+/// loops over a constant range are unrolled. The outputs are driven after a *delta-cycle*, or
+/// stored in registers on the rising edge of the `clocks` arguments if there are any.
+pub fn lower(entity_fn: &ItemFn, clocks: &HashSet<Ident>) -> syn::Result<Module> {
+    let name = entity_fn.sig.ident.to_string();
+    let mut sig = Signature::new();
+    let mut inputs = Vec::new();
+    for arg in entity_fn.sig.inputs.iter() {
+        match arg {
+            FnArg::Receiver(receiver) => {
+                return Err(Error::new(
+                    receiver.span(),
+                    "Methods cannot be lowered to LLHD.",
+                ))
+            }
+            FnArg::Typed(arg) => {
+                let ident = match &*arg.pat {
+                    Pat::Ident(ident) => ident.ident.to_string(),
+                    pat => return Err(Error::new(pat.span(), "Expected an argument name.")),
+                };
+                let ty = Ty::parse(&arg.ty)?;
+                if ty != Ty::BOOL && clocks.iter().any(|clock| *clock == ident) {
+                    return Err(Error::new(
+                        arg.ty.span(),
+                        "A clock must be a `bool` argument.",
+                    ));
+                }
+                sig.add_input(signal_ty(int_ty(ty.width)));
+                inputs.push((ident, ty));
+            }
+        }
+    }
+    let outputs = match &entity_fn.sig.output {
+        ReturnType::Default => Vec::new(),
+        ReturnType::Type(_, ty) => match &**ty {
+            Type::Tuple(tuple) => tuple
+                .elems
+                .iter()
+                .map(Ty::parse)
+                .collect::<syn::Result<_>>()?,
+            ty => vec![Ty::parse(ty)?],
+        },
+    };
+    if outputs.is_empty() {
+        return Err(Error::new(
+            entity_fn.sig.span(),
+            "An entity must return a value to be lowered to LLHD.",
+        ));
+    }
+    for ty in outputs.iter() {
+        sig.add_output(signal_ty(int_ty(ty.width)));
+    }
+
+    let mut data = UnitData::new(UnitKind::Entity, UnitName::global(&name), sig);
+    {
+        let mut unit = UnitBuilder::new_anonymous(&mut data);
+        let halt = unit
+            .first_block()
+            .and_then(|bb| unit.last_inst(bb))
+            .expect("A new entity holds a halt instruction.");
+        unit.insert_before(halt);
+
+        let mut scope = Scope::new();
+        let mut triggers = Vec::new();
+        for (i, (ident, ty)) in inputs.into_iter().enumerate() {
+            let signal = unit.input_arg(i);
+            unit.set_name(signal, ident.clone());
+            let value = unit.ins().prb(signal);
+            if clocks.iter().any(|clock| *clock == ident) {
+                triggers.push(value);
+            }
+            scope.insert(ident, Binding::Value(value, ty));
+        }
+        let single = outputs.len() == 1;
+        let results = {
+            let mut lowering = Lowering { unit: &mut unit };
+            match (single, outputs.as_slice()) {
+                (true, [ty]) => vec![lowering.block(&entity_fn.block, &scope, Some(*ty))?.0],
+                _ => lowering.tuple_block(&entity_fn.block, &scope, &outputs)?,
+            }
+        };
+        let delay = if triggers.is_empty() {
+            let mut delta = TimeValue::zero();
+            delta.delta = 1;
+            Some(unit.ins().const_time(delta))
+        } else {
+            None
+        };
+        for (i, result) in results.into_iter().enumerate() {
+            let signal = unit.output_arg(i);
+            unit.set_name(
+                signal,
+                if single {
+                    "out".to_string()
+                } else {
+                    format!("out{}", i)
+                },
+            );
+            match delay {
+                Some(delay) => {
+                    unit.ins().drv(signal, result, delay);
+                }
+                None => {
+                    let triggers = triggers
+                        .iter()
+                        .map(|&trigger| RegTrigger {
+                            data: result,
+                            mode: RegMode::Rise,
+                            trigger,
+                            gate: None,
+                        })
+                        .collect();
+                    unit.ins().reg(signal, triggers);
+                }
+            }
+        }
+    }
+
+    let mut verifier = Verifier::new();
+    verifier.verify_unit(llhd::ir::Unit::new_anonymous(&data));
+    if let Err(errors) = verifier.finish() {
+        return Err(Error::new(
+            entity_fn.sig.span(),
+            format!("Invalid LLHD generated:\n{}", errors),
+        ));
+    }
+    let mut module = Module::new();
+    module.add_unit(data);
+    Ok(module)
+}
+
+struct Lowering<'u, 'a> {
+    unit: &'u mut UnitBuilder<'a>,
+}
+
+impl Lowering<'_, '_> {
+    /// Lower the statements of a block, then its tail expression.
+    fn block(
+        &mut self,
+        block: &Block,
+        scope: &Scope,
+        expected: Option<Ty>,
+    ) -> syn::Result<(Value, Ty)> {
+        let mut scope = scope.clone();
+        let tail = self.statements(block, &mut scope)?;
+        self.expr(tail, &scope, expected)
+    }
+
+    /// Lower a block whose tail expression is a tuple.
+    fn tuple_block(
+        &mut self,
+        block: &Block,
+        scope: &Scope,
+        types: &[Ty],
+    ) -> syn::Result<Vec<Value>> {
+        let mut scope = scope.clone();
+        let tail = self.statements(block, &mut scope)?;
+        match tail {
+            Expr::Tuple(tuple) if tuple.elems.len() == types.len() => tuple
+                .elems
+                .iter()
+                .zip(types)
+                .map(|(elem, ty)| Ok(self.expr(elem, &scope, Some(*ty))?.0))
+                .collect(),
+            tail => Err(Error::new(
+                tail.span(),
+                "Expected a tuple of the return types.",
+            )),
+        }
+    }
+
+    /// Lower the statements of a block, and return its tail expression.
+    ///
+    /// Only the bindings declared in the block can be assigned, so that the assignments in the
+    /// branches of an `if` or a `match` are not applied unconditionally.
+    fn statements<'b>(&mut self, block: &'b Block, scope: &mut Scope) -> syn::Result<&'b Expr> {
+        let (tail, stmts) = match block.stmts.split_last() {
+            Some((Stmt::Expr(tail), stmts)) => (tail, stmts),
+            _ => {
+                return Err(Error::new(
+                    block.span(),
+                    "Expected a block ending with an expression.",
+                ))
+            }
+        };
+        let mut assignable = HashSet::new();
+        for stmt in stmts {
+            self.statement(stmt, scope, &mut assignable)?;
+        }
+        Ok(tail)
+    }
+
+    /// Lower a `let` statement, an assignment or a `for` loop, updating the bindings in scope.
+    fn statement(
+        &mut self,
+        stmt: &Stmt,
+        scope: &mut Scope,
+        assignable: &mut HashSet<String>,
+    ) -> syn::Result<()> {
+        let expr =
+            match stmt {
+                Stmt::Local(local) => {
+                    let (pat, ty) = match &local.pat {
+                        Pat::Type(pat_type) => (&*pat_type.pat, Some(Ty::parse(&pat_type.ty)?)),
+                        pat => (pat, None),
+                    };
+                    let ident = match pat {
+                        Pat::Ident(ident) if ident.by_ref.is_none() && ident.subpat.is_none() => {
+                            ident.ident.to_string()
+                        }
+                        pat => {
+                            return Err(Error::new(
+                                pat.span(),
+                                "Only simple bindings can be lowered to LLHD.",
+                            ))
+                        }
+                    };
+                    let init = match &local.init {
+                        Some((_, init)) => init,
+                        None => {
+                            return Err(Error::new(
+                                local.span(),
+                                "A binding must be initialized to be lowered to LLHD.",
+                            ))
+                        }
+                    };
+                    let (value, ty) = self.expr(init, scope, ty)?;
+                    self.unit.set_name(value, ident.clone());
+                    scope.insert(ident.clone(), Binding::Value(value, ty));
+                    assignable.insert(ident);
+                    return Ok(());
+                }
+                Stmt::Semi(expr, _) | Stmt::Expr(expr) => expr,
+                Stmt::Item(item) => return Err(Error::new(
+                    item.span(),
+                    "Only `let` statements, assignments and `for` loops can be lowered to LLHD.",
+                )),
+            };
+        let (ident, value) =
+            match expr {
+                Expr::Assign(assign) => {
+                    let (ident, ty) = assigned(&assign.left, scope, assignable)?;
+                    (ident, self.expr(&assign.right, scope, Some(ty))?)
+                }
+                Expr::AssignOp(assign) => {
+                    let (ident, ty) = assigned(&assign.left, scope, assignable)?;
+                    (
+                        ident,
+                        self.binary(&assign.op, &assign.left, &assign.right, scope, Some(ty))?,
+                    )
+                }
+                Expr::ForLoop(for_loop) => return self.for_loop(for_loop, scope, assignable),
+                expr => return Err(Error::new(
+                    expr.span(),
+                    "Only `let` statements, assignments and `for` loops can be lowered to LLHD.",
+                )),
+            };
+        self.unit.set_name(value.0, ident.clone());
+        scope.insert(ident, Binding::Value(value.0, value.1));
+        Ok(())
+    }
+
+    /// Unroll a `for` loop over a constant range.
+    ///
+    /// The body is lowered once per index, and the bindings of the enclosing block it assigns are
+    /// carried to the next iteration.
+    fn for_loop(
+        &mut self,
+        for_loop: &ExprForLoop,
+        scope: &mut Scope,
+        assignable: &HashSet<String>,
+    ) -> syn::Result<()> {
+        let index = match &for_loop.pat {
+            Pat::Ident(ident) if ident.by_ref.is_none() && ident.subpat.is_none() => {
+                Some(ident.ident.to_string())
+            }
+            Pat::Wild(_) => None,
+            pat => {
+                return Err(Error::new(
+                    pat.span(),
+                    "Only simple bindings can be lowered to LLHD.",
+                ))
+            }
+        };
+        let range = match &*for_loop.expr {
+            Expr::Range(range) => range,
+            expr => {
+                return Err(Error::new(
+                    expr.span(),
+                    "Only `for` loops over a constant range can be lowered to LLHD.",
+                ))
+            }
+        };
+        let start = bound(range.from.as_deref(), range)?;
+        let end = match range.limits {
+            RangeLimits::HalfOpen(_) => bound(range.to.as_deref(), range)?,
+            RangeLimits::Closed(_) => bound(range.to.as_deref(), range)? + 1,
+        };
+        if end.saturating_sub(start) > MAX_ITERATIONS {
+            return Err(Error::new(
+                range.span(),
+                format!(
+                    "A `for` loop must have at most {} iterations to be lowered to LLHD.",
+                    MAX_ITERATIONS
+                ),
+            ));
+        }
+        let mut carried = assignable.clone();
+        if let Some(index) = &index {
+            carried.remove(index);
+        }
+        for stmt in for_loop.body.stmts.iter() {
+            if let Stmt::Local(local) = stmt {
+                let pat = match &local.pat {
+                    Pat::Type(pat_type) => &*pat_type.pat,
+                    pat => pat,
+                };
+                match pat {
+                    Pat::Ident(ident) if carried.contains(&ident.ident.to_string()) => {
+                        return Err(Error::new(ident.span(), "Shadowing a binding assigned in a `for` loop cannot be lowered to LLHD."))
+                    }
+                    _ => (),
+                }
+            }
+        }
+        for i in start..end {
+            let mut body = scope.clone();
+            if let Some(index) = &index {
+                body.insert(index.clone(), Binding::Index(i));
+            }
+            let mut body_assignable = carried.clone();
+            for stmt in for_loop.body.stmts.iter() {
+                self.statement(stmt, &mut body, &mut body_assignable)?;
+            }
+            for ident in carried.iter() {
+                scope.insert(ident.clone(), body[ident]);
+            }
+        }
+        Ok(())
+    }
+
+    /// Lower an expression, of the expected type if known.
+    fn expr(
+        &mut self,
+        expr: &Expr,
+        scope: &Scope,
+        expected: Option<Ty>,
+    ) -> syn::Result<(Value, Ty)> {
+        match expr {
+            Expr::Paren(paren) => self.expr(&paren.expr, scope, expected),
+            Expr::Group(group) => self.expr(&group.expr, scope, expected),
+            Expr::Block(block) => self.block(&block.block, scope, expected),
+            Expr::Path(path) => match path
+                .path
+                .get_ident()
+                .and_then(|ident| scope.get(&ident.to_string()))
+            {
+                Some(Binding::Value(value, ty)) => Ok((*value, *ty)),
+                Some(Binding::Index(index)) => {
+                    let ty = expected.ok_or_else(|| {
+                        Error::new(
+                            path.span(),
+                            "The type of this loop index cannot be inferred, add a cast.",
+                        )
+                    })?;
+                    Ok((
+                        self.unit
+                            .ins()
+                            .const_int(IntValue::from_usize(ty.width, *index)),
+                        ty,
+                    ))
+                }
+                None => Err(Error::new(path.span(), "Unknown binding.")),
+            },
+            Expr::Lit(lit) => self.literal(&lit.lit, expected),
+            Expr::Cast(cast) => {
+                let to = Ty::parse(&cast.ty)?;
+                let (x, from) =
+                    self.expr(&cast.expr, scope, infer(&cast.expr, scope).or(Some(to)))?;
+                Ok((self.cast(x, from, to), to))
+            }
+            Expr::Unary(unary) => {
+                let (x, ty) = self.expr(&unary.expr, scope, expected)?;
+                match unary.op {
+                    UnOp::Not(_) => Ok((self.unit.ins().not(x), ty)),
+                    UnOp::Neg(_) if ty.signed => Ok((self.unit.ins().neg(x), ty)),
+                    op => Err(Error::new(
+                        op.span(),
+                        "This operator cannot be lowered to LLHD.",
+                    )),
+                }
+            }
+            Expr::Binary(binary) => {
+                self.binary(&binary.op, &binary.left, &binary.right, scope, expected)
+            }
+            Expr::If(expr_if) => {
+                let (cond, _) = self.expr(&expr_if.cond, scope, Some(Ty::BOOL))?;
+                let expected = expected.or_else(|| infer(expr, scope));
+                let (then, ty) = self.block(&expr_if.then_branch, scope, expected)?;
+                let (otherwise, _) = match &expr_if.else_branch {
+                    Some((_, otherwise)) => self.expr(otherwise, scope, Some(ty))?,
+                    None => {
+                        return Err(Error::new(
+                            expr_if.span(),
+                            "An `if` must have an `else` branch to be lowered to LLHD.",
+                        ))
+                    }
+                };
+                Ok((self.select(cond, then, otherwise), ty))
+            }
+            Expr::Match(expr_match) => {
+                let (scrutinee, scrutinee_ty) =
+                    self.expr(&expr_match.expr, scope, infer(&expr_match.expr, scope))?;
+                let expected = expected.or_else(|| infer(expr, scope));
+                let (last, arms) = expr_match.arms.split_last().ok_or_else(|| {
+                    Error::new(
+                        expr_match.span(),
+                        "A `match` must have arms to be lowered to LLHD.",
+                    )
+                })?;
+                // The last arm is the default value, the previous arms override it in reverse order.
+                let (mut result, ty) = self.expr(&last.body, scope, expected)?;
+                for arm in arms.iter().rev() {
+                    if let Some((guard, _)) = &arm.guard {
+                        return Err(Error::new(
+                            guard.span(),
+                            "Match guards cannot be lowered to LLHD.",
+                        ));
+                    }
+                    let cond = self.pattern(&arm.pat, scrutinee, scrutinee_ty)?;
+                    let (value, _) = self.expr(&arm.body, scope, Some(ty))?;
+                    result = self.select(cond, value, result);
+                }
+                Ok((result, ty))
+            }
+            expr => Err(Error::new(
+                expr.span(),
+                "This expression cannot be lowered to LLHD.",
+            )),
+        }
+    }
+
+    fn literal(&mut self, lit: &Lit, expected: Option<Ty>) -> syn::Result<(Value, Ty)> {
+        match lit {
+            Lit::Bool(value) => Ok((
+                self.unit
+                    .ins()
+                    .const_int(IntValue::from_usize(1, value.value as usize)),
+                Ty::BOOL,
+            )),
+            Lit::Int(int) => {
+                let ty = match int.suffix() {
+                    "" => expected,
+                    suffix => Ty::from_name(suffix),
+                }
+                .ok_or_else(|| {
+                    Error::new(
+                        int.span(),
+                        "The type of this literal cannot be inferred, add a suffix.",
+                    )
+                })?;
+                let value: usize = int.base10_parse()?;
+                Ok((
+                    self.unit
+                        .ins()
+                        .const_int(IntValue::from_usize(ty.width, value)),
+                    ty,
+                ))
+            }
+            lit => Err(Error::new(
+                lit.span(),
+                "This literal cannot be lowered to LLHD.",
+            )),
+        }
+    }
+
+    fn binary(
+        &mut self,
+        op: &BinOp,
+        left: &Expr,
+        right: &Expr,
+        scope: &Scope,
+        expected: Option<Ty>,
+    ) -> syn::Result<(Value, Ty)> {
+        if let BinOp::Shl(_) | BinOp::Shr(_) | BinOp::ShlEq(_) | BinOp::ShrEq(_) = op {
+            // The shift amount has its own type.
+            let (x, ty) = self.expr(left, scope, expected.or_else(|| infer(left, scope)))?;
+            let (amount, _) = self.expr(right, scope, infer(right, scope).or(Some(ty)))?;
+            let left = matches!(op, BinOp::Shl(_) | BinOp::ShlEq(_));
+            // The bits shifted in are zeros, or copies of the sign bit for an arithmetic shift.
+            let hidden = if ty.signed && !left {
+                self.sign(x, ty, ty.width)
+            } else {
+                self.unit.ins().const_int(IntValue::zero(ty.width))
+            };
+            let value = if left {
+                self.unit.ins().shl(x, hidden, amount)
+            } else {
+                self.unit.ins().shr(x, hidden, amount)
+            };
+            return Ok((value, ty));
+        }
+        let comparison = matches!(
+            op,
+            BinOp::Eq(_) | BinOp::Ne(_) | BinOp::Lt(_) | BinOp::Le(_) | BinOp::Gt(_) | BinOp::Ge(_)
+        );
+        let operand = if comparison {
+            infer(left, scope).or_else(|| infer(right, scope))
+        } else {
+            expected
+                .or_else(|| infer(left, scope))
+                .or_else(|| infer(right, scope))
+        };
+        let (x, ty) = self.expr(left, scope, operand)?;
+        let (y, right_ty) = self.expr(right, scope, Some(ty))?;
+        if right_ty != ty {
+            return Err(Error::new(
+                right.span(),
+                "The operands must have the same type to be lowered to LLHD.",
+            ));
+        }
+        let mut ins = self.unit.ins();
+        let value = match op {
+            BinOp::Add(_) | BinOp::AddEq(_) => ins.add(x, y),
+            BinOp::Sub(_) | BinOp::SubEq(_) => ins.sub(x, y),
+            BinOp::Mul(_) | BinOp::MulEq(_) if ty.signed => ins.smul(x, y),
+            BinOp::Mul(_) | BinOp::MulEq(_) => ins.umul(x, y),
+            BinOp::Div(_) | BinOp::DivEq(_) if ty.signed => ins.sdiv(x, y),
+            BinOp::Div(_) | BinOp::DivEq(_) => ins.udiv(x, y),
+            BinOp::Rem(_) | BinOp::RemEq(_) if ty.signed => ins.srem(x, y),
+            BinOp::Rem(_) | BinOp::RemEq(_) => ins.urem(x, y),
+            BinOp::BitAnd(_) | BinOp::BitAndEq(_) | BinOp::And(_) => ins.and(x, y),
+            BinOp::BitOr(_) | BinOp::BitOrEq(_) | BinOp::Or(_) => ins.or(x, y),
+            BinOp::BitXor(_) | BinOp::BitXorEq(_) => ins.xor(x, y),
+            BinOp::Eq(_) => ins.eq(x, y),
+            BinOp::Ne(_) => ins.neq(x, y),
+            BinOp::Lt(_) if ty.signed => ins.slt(x, y),
+            BinOp::Lt(_) => ins.ult(x, y),
+            BinOp::Le(_) if ty.signed => ins.sle(x, y),
+            BinOp::Le(_) => ins.ule(x, y),
+            BinOp::Gt(_) if ty.signed => ins.sgt(x, y),
+            BinOp::Gt(_) => ins.ugt(x, y),
+            BinOp::Ge(_) if ty.signed => ins.sge(x, y),
+            BinOp::Ge(_) => ins.uge(x, y),
+            op => {
+                return Err(Error::new(
+                    op.span(),
+                    "This operator cannot be lowered to LLHD.",
+                ))
+            }
+        };
+        Ok((value, if comparison { Ty::BOOL } else { ty }))
+    }
+
+    /// Returns the condition of a `match` arm pattern.
+    fn pattern(&mut self, pat: &Pat, scrutinee: Value, ty: Ty) -> syn::Result<Value> {
+        match pat {
+            Pat::Lit(lit) => {
+                let (value, _) = match &*lit.expr {
+                    Expr::Lit(lit) => self.literal(&lit.lit, Some(ty))?,
+                    expr => {
+                        return Err(Error::new(
+                            expr.span(),
+                            "Only literal patterns can be lowered to LLHD.",
+                        ))
+                    }
+                };
+                Ok(self.unit.ins().eq(scrutinee, value))
+            }
+            Pat::Or(or) => {
+                let mut cases = or.cases.iter();
+                let first = cases.next().expect("An or-pattern has at least one case.");
+                let mut cond = self.pattern(first, scrutinee, ty)?;
+                for case in cases {
+                    let other = self.pattern(case, scrutinee, ty)?;
+                    cond = self.unit.ins().or(cond, other);
+                }
+                Ok(cond)
+            }
+            Pat::Wild(_) => Ok(self.unit.ins().const_int(IntValue::from_usize(1, 1))),
+            pat => Err(Error::new(
+                pat.span(),
+                "Only literal patterns can be lowered to LLHD.",
+            )),
+        }
+    }
+
+    /// Convert an integer to another width, truncating it or extending it with its sign.
+    fn cast(&mut self, x: Value, from: Ty, to: Ty) -> Value {
+        if to.width <= from.width {
+            return if to.width == from.width {
+                x
+            } else {
+                self.unit.ins().ext_slice(x, 0, to.width)
+            };
+        }
+        let high = if from.signed {
+            self.sign(x, from, to.width)
+        } else {
+            self.unit.ins().const_int(IntValue::zero(to.width))
+        };
+        self.unit.ins().ins_slice(high, x, 0, from.width)
+    }
+
+    /// Returns `width` copies of the sign bit of `x`.
+    fn sign(&mut self, x: Value, ty: Ty, width: usize) -> Value {
+        let sign = self.unit.ins().ext_slice(x, ty.width - 1, 1);
+        let ones = self.unit.ins().const_int(IntValue::all_ones(width));
+        let zero = self.unit.ins().const_int(IntValue::zero(width));
+        self.select(sign, ones, zero)
+    }
+
+    /// Returns `then` if `cond` is true, `otherwise` if not.
+    fn select(&mut self, cond: Value, then: Value, otherwise: Value) -> Value {
+        let choices = self.unit.ins().array(vec![otherwise, then]);
+        self.unit.ins().mux(choices, cond)
+    }
+}
+
+/// Returns the binding assigned by an assignment, and its type.
+fn assigned(
+    place: &Expr,
+    scope: &Scope,
+    assignable: &HashSet<String>,
+) -> syn::Result<(String, Ty)> {
+    let ident = match place {
+        Expr::Path(path) => path.path.get_ident().map(|ident| ident.to_string()),
+        _ => None,
+    };
+    match ident.as_ref().filter(|ident| assignable.contains(*ident)).map(|ident| (ident, scope.get(ident))) {
+        Some((ident, Some(Binding::Value(_, ty)))) => Ok((ident.clone(), *ty)),
+        _ => Err(Error::new(
+            place.span(),
+            "Only the bindings of the enclosing block or `for` loop can be assigned to be lowered to LLHD.",
+        )),
+    }
+}
+
+/// Returns the value of a bound of a constant range.
+fn bound(expr: Option<&Expr>, range: &syn::ExprRange) -> syn::Result<usize> {
+    match expr {
+        Some(Expr::Paren(paren)) => bound(Some(&paren.expr), range),
+        Some(Expr::Group(group)) => bound(Some(&group.expr), range),
+        Some(Expr::Lit(syn::ExprLit {
+            lit: Lit::Int(int), ..
+        })) => int.base10_parse(),
+        Some(expr) => Err(Error::new(
+            expr.span(),
+            "The bounds of a `for` loop must be literals to be lowered to LLHD.",
+        )),
+        None => Err(Error::new(
+            range.span(),
+            "The bounds of a `for` loop must be literals to be lowered to LLHD.",
+        )),
+    }
+}
+
+/// Infer the type of an expression without lowering it, if it does not depend on the context.
+fn infer(expr: &Expr, scope: &Scope) -> Option<Ty> {
+    match expr {
+        Expr::Paren(paren) => infer(&paren.expr, scope),
+        Expr::Group(group) => infer(&group.expr, scope),
+        Expr::Block(block) => match block.block.stmts.last() {
+            // The bindings of the block are not known yet, so only literals are inferred.
+            Some(Stmt::Expr(tail)) if block.block.stmts.len() == 1 => infer(tail, scope),
+            _ => None,
+        },
+        Expr::Path(path) => {
+            path.path
+                .get_ident()
+                .and_then(|ident| match scope.get(&ident.to_string()) {
+                    Some(Binding::Value(_, ty)) => Some(*ty),
+                    _ => None,
+                })
+        }
+        Expr::Lit(lit) => match &lit.lit {
+            Lit::Bool(_) => Some(Ty::BOOL),
+            Lit::Int(int) => Ty::from_name(int.suffix()),
+            _ => None,
+        },
+        Expr::Unary(unary) => infer(&unary.expr, scope),
+        Expr::Cast(cast) => Ty::parse(&cast.ty).ok(),
+        Expr::Binary(binary) => match binary.op {
+            BinOp::Shl(_) | BinOp::Shr(_) => infer(&binary.left, scope),
+            BinOp::Eq(_)
+            | BinOp::Ne(_)
+            | BinOp::Lt(_)
+            | BinOp::Le(_)
+            | BinOp::Gt(_)
+            | BinOp::Ge(_) => Some(Ty::BOOL),
+            _ => infer(&binary.left, scope).or_else(|| infer(&binary.right, scope)),
+        },
+        Expr::If(expr_if) => match &expr_if.else_branch {
+            Some((_, otherwise)) => expr_if
+                .then_branch
+                .stmts
+                .last()
+                .and_then(|stmt| match stmt {
+                    Stmt::Expr(tail) if expr_if.then_branch.stmts.len() == 1 => infer(tail, scope),
+                    _ => None,
+                })
+                .or_else(|| infer(otherwise, scope)),
+            None => None,
+        },
+        Expr::Match(expr_match) => expr_match
+            .arms
+            .iter()
+            .find_map(|arm| infer(&arm.body, scope)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    fn assembly(entity_fn: proc_macro2::TokenStream) -> String {
+        assembly_clocked(entity_fn, &[])
+    }
+
+    fn assembly_clocked(entity_fn: proc_macro2::TokenStream, clocks: &[&str]) -> String {
+        let clocks = clocks
+            .iter()
+            .map(|clock| quote::format_ident!("{}", clock))
+            .collect();
+        let module = lower(&syn::parse2(entity_fn).unwrap(), &clocks).unwrap();
+        llhd::assembly::write_module_string(&module)
+    }
+
+    fn error(entity_fn: proc_macro2::TokenStream) -> String {
+        match lower(&syn::parse2(entity_fn).unwrap(), &HashSet::new()) {
+            Ok(_) => panic!("The entity was lowered."),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn lower_adder() {
+        assert_eq!(
+            assembly(quote! {
+                fn adder(a: i32, b: i32) -> i32 {
+                    a + b
+                }
+            }),
+            "entity @adder (i32$ %a, i32$ %b) -> (i32$ %out) {
+    %0 = prb i32$ %a
+    %1 = prb i32$ %b
+    %2 = add i32 %0, %1
+    %3 = const time 0s 1d
+    drv i32$ %out, %2, %3
+}
+"
+        );
+    }
+
+    #[test]
+    fn lower_let_if_and_match() {
+        let asm = assembly(quote! {
+            fn saturate(a: u8, mode: u8) -> (u8, bool) {
+                let limit = match mode {
+                    0 | 1 => 15,
+                    _ => 255u8,
+                };
+                let over = a > limit;
+                (if over { limit } else { a }, over)
+            }
+        });
+        llhd::assembly::parse_module(&asm).unwrap();
+        assert!(asm.starts_with("entity @saturate (i8$ %a, i8$ %mode) -> (i8$ %out0, i1$ %out1) {"));
+        assert!(asm.contains("ugt i8"));
+        assert!(asm.contains("mux [2 x i8]"));
+    }
+
+    #[test]
+    fn lower_clocked() {
+        assert_eq!(
+            assembly_clocked(
+                quote! {
+                    fn counter(clk: bool, count: u8) -> u8 {
+                        count + 1
+                    }
+                },
+                &["clk"]
+            ),
+            "entity @counter (i1$ %clk, i8$ %count) -> (i8$ %out) {
+    %0 = prb i1$ %clk
+    %1 = prb i8$ %count
+    %2 = const i8 1
+    %3 = add i8 %1, %2
+    reg i8$ %out, [%3, rise %0]
+}
+"
+        );
+    }
+
+    #[test]
+    fn lower_shifts_and_casts() {
+        let asm = assembly(quote! {
+            fn scale(a: u8, b: i8) -> (u16, i16, u8, i8) {
+                ((a as u16) << 4, b as i16 >> 1u8, (a >> 2) as u8, (a as i16 >> 8u8) as i8)
+            }
+        });
+        llhd::assembly::parse_module(&asm).unwrap();
+        assert!(asm.contains("shl i16"));
+        assert!(asm.contains("shr i16"));
+        assert!(asm.contains("inss i16 %2, i8 %0, 0, 8"));
+        assert!(asm.contains("exts i8, i16"));
+        // The sign of `b` is extended.
+        assert!(asm.contains("exts i1, i8 %1, 7, 1"));
+    }
+
+    #[test]
+    fn lower_for() {
+        assert_eq!(
+            assembly(quote! {
+                fn count_ones(a: u8) -> u8 {
+                    let mut count = 0u8;
+                    for i in 0..2 {
+                        let bit = (a >> i) & 1;
+                        count += bit;
+                    }
+                    count
+                }
+            }),
+            "entity @count_ones (i8$ %a) -> (i8$ %out) {
+    %0 = prb i8$ %a
+    %count = const i8 0
+    %1 = const i8 0
+    %2 = const i8 0
+    %3 = shr i8 %0, i8 %2, i8 %1
+    %4 = const i8 1
+    %bit = and i8 %3, %4
+    %count1 = add i8 %count, %bit
+    %5 = const i8 1
+    %6 = const i8 0
+    %7 = shr i8 %0, i8 %6, i8 %5
+    %8 = const i8 1
+    %bit1 = and i8 %7, %8
+    %count2 = add i8 %count1, %bit1
+    %9 = const time 0s 1d
+    drv i8$ %out, %count2, %9
+}
+"
+        );
+    }
+
+    #[test]
+    fn lower_errors() {
+        assert_eq!(
+            "Only booleans and fixed width integers can be lowered to LLHD.",
+            error(quote! { fn f(a: f32) -> i32 { 0 } })
+        );
+        assert_eq!(
+            "This expression cannot be lowered to LLHD.",
+            error(quote! { fn f(a: i32) -> i32 { g(a) } })
+        );
+        assert_eq!(
+            "The type of this literal cannot be inferred, add a suffix.",
+            error(quote! { fn f(a: bool) -> bool { 1 == 1 } })
+        );
+        assert_eq!(
+            "Only `for` loops over a constant range can be lowered to LLHD.",
+            error(quote! { fn f(a: u8) -> u8 { for i in a { } a } })
+        );
+        assert_eq!(
+            "The bounds of a `for` loop must be literals to be lowered to LLHD.",
+            error(quote! { fn f(a: u8) -> u8 { for i in 0..a { } a } })
+        );
+        assert_eq!(
+            "Only the bindings of the enclosing block or `for` loop can be assigned to be lowered to LLHD.",
+            error(quote! { fn f(a: bool) -> u8 { let mut x = 0u8; let y = if a { x = 1; x } else { x }; y } })
+        );
+    }
+}
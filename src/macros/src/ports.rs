@@ -1,15 +1,16 @@
 use proc_macro2::{Ident, TokenStream};
 use quote::{quote, quote_spanned, ToTokens};
-use syn::{Token, Visibility};
+use syn::{Token, Type, Visibility};
 use syn::parse::{Parse, Parser, ParseStream};
 use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
 
 use crate::way::Way;
 
 struct Port {
     visibility: Visibility,
     name: Ident,
-    ty: Ident,
+    ty: Type,
     way: Way,
 }
 
@@ -18,7 +19,7 @@ impl Parse for Port {
         let visibility: Visibility = input.parse()?;
         let name: Ident = input.parse()?;
         let way: Way = input.parse()?;
-        let ty: Ident = input.parse()?;
+        let ty: Type = input.parse()?;
         Ok(Port {
             visibility,
             name,
@@ -30,7 +31,10 @@ impl Parse for Port {
 
 pub fn ports(input: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
     let parser = Punctuated::<Port, Token![,]>::parse_terminated;
-    let ports = parser.parse2(input).unwrap();
+    let ports = match parser.parse2(input) {
+        Ok(ports) => ports,
+        Err(err) => return err.to_compile_error(),
+    };
     let fields = ports.iter().map(|port| {
         let visibility = match port.visibility {
             Visibility::Inherited => quote! { pub(crate) },
@@ -40,10 +44,13 @@ pub fn ports(input: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
         let ty = &port.ty;
         let port_type = match port.way {
             Way::In => quote_spanned! {ty.span()=>
-                system_rust::port::In<#ty>
+                ::sand::ports::In<#ty>
             },
             Way::Out => quote_spanned! {ty.span()=>
-                system_rust::port::Out<#ty>
+                ::sand::ports::Out<#ty>
+            },
+            Way::InOut => quote_spanned! {ty.span()=>
+                ::sand::ports::InOut<#ty>
             }
         };
         quote!(
@@ -76,13 +83,29 @@ mod tests {
         );
         assert_eq!(generated.to_string(), quote!(
         pub(crate) struct Ports {
-            pub(crate) first_out: system_rust::port::Out<i32>,
-            pub(crate) second_out: system_rust::port::Out<u64>,
-            pub(crate) third_in: system_rust::port::In<u8>,
-            pub(crate) fourth_in: system_rust::port::In<i16>,
+            pub(crate) first_out: ::sand::ports::Out<i32>,
+            pub(crate) second_out: ::sand::ports::Out<u64>,
+            pub(crate) third_in: ::sand::ports::In<u8>,
+            pub(crate) fourth_in: ::sand::ports::In<i16>,
         }
     ).to_string());
         let mut file = File::create("test_ports.rs").unwrap();
         file.write_all(format!("{}", generated).as_bytes()).unwrap();
     }
+
+    #[test]
+    fn ports_inout_test() {
+        let generated = ports(quote!(
+            sda <-> StdLogic,
+            scl <- bool,
+            data -> LogicVector<8>
+        ));
+        assert_eq!(generated.to_string(), quote!(
+        pub(crate) struct Ports {
+            pub(crate) sda: ::sand::ports::InOut<StdLogic>,
+            pub(crate) scl: ::sand::ports::In<bool>,
+            pub(crate) data: ::sand::ports::Out<LogicVector<8> >,
+        }
+    ).to_string());
+    }
 }
@@ -0,0 +1,215 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::spanned::Spanned;
+use syn::{parse_quote, Data, DeriveInput, Error, Fields, Index, Member, Type};
+
+/// Implement `sand::Signal` and `sand::trace::Trace` for a struct of fixed size fields.
+///
+/// The fields are packed as a SystemVerilog packed struct, the first field holding the most
+/// significant bits, and each field is traced as its own waveform variable.
+pub fn derive_signal(input: TokenStream) -> TokenStream {
+    let mut item: DeriveInput = match syn::parse2(input) {
+        Ok(item) => item,
+        Err(err) => return err.to_compile_error(),
+    };
+    let fields = match &item.data {
+        Data::Struct(data) => data.fields.clone(),
+        Data::Enum(data) => {
+            return Error::new(data.enum_token.span, "Only structs can derive `Signal`.")
+                .to_compile_error()
+        }
+        Data::Union(data) => {
+            return Error::new(data.union_token.span, "Only structs can derive `Signal`.")
+                .to_compile_error()
+        }
+    };
+
+    let mut errors = TokenStream::new();
+    let mut members = Vec::new();
+    let mut types = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        if let Some(kind) = unsized_type(&field.ty) {
+            let name = match &field.ident {
+                Some(ident) => ident.to_string(),
+                None => index.to_string(),
+            };
+            let message = format!(
+                "The field `{}` is {} which has no fixed size, please use a fixed size type such as an array.",
+                name, kind
+            );
+            errors.extend(quote_spanned! {field.ty.span()=> compile_error!(#message);});
+        }
+        members.push(match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index {
+                index: index as u32,
+                span: Span::call_site(),
+            }),
+        });
+        types.push(&field.ty);
+    }
+    if !errors.is_empty() {
+        return errors;
+    }
+
+    if !item.generics.params.is_empty() {
+        let where_clause = item.generics.make_where_clause();
+        for ty in types.iter() {
+            where_clause
+                .predicates
+                .push(parse_quote!(#ty: ::sand::Signal));
+        }
+    }
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+
+    let var_names = members.iter().map(|member| match member {
+        Member::Named(ident) => ident.to_string(),
+        Member::Unnamed(index) => index.index.to_string(),
+    });
+    let locals: Vec<_> = (0..members.len())
+        .map(|index| format_ident!("field_{}", index))
+        .collect();
+    // The last field holds the least significant bits.
+    let packs = members.iter().zip(types.iter()).rev().map(|(member, ty)| {
+        quote! {
+            let high = low + <#ty as ::sand::trace::Trace>::WIDTH;
+            ::sand::Signal::pack_bits(&self.#member, &mut bits[low..high]);
+            low = high;
+        }
+    });
+    let unpacks = locals.iter().zip(types.iter()).rev().map(|(local, ty)| {
+        quote! {
+            let high = low + <#ty as ::sand::trace::Trace>::WIDTH;
+            let #local = <#ty as ::sand::Signal>::unpack_bits(&bits[low..high])?;
+            low = high;
+        }
+    });
+    let constructor = match &fields {
+        Fields::Named(_) => quote!(#name { #(#members: #locals),* }),
+        Fields::Unnamed(_) => quote!(#name(#(#locals),*)),
+        Fields::Unit => name.to_token_stream(),
+    };
+
+    quote! {
+        impl #impl_generics ::sand::trace::Trace for #name #ty_generics #where_clause {
+            const WIDTH: usize = 0 #(+ <#types as ::sand::trace::Trace>::WIDTH)*;
+
+            fn trace_bits(&self, bits: &mut ::std::string::String) {
+                #(::sand::trace::Trace::trace_bits(&self.#members, bits);)*
+            }
+
+            fn trace_vars(name: &str, vars: &mut ::std::vec::Vec<(::std::string::String, usize)>) {
+                #(<#types as ::sand::trace::Trace>::trace_vars(&::std::format!("{}.{}", name, #var_names), vars);)*
+            }
+        }
+
+        impl #impl_generics ::sand::Signal for #name #ty_generics #where_clause {
+            #[allow(unused_variables, unused_mut, unused_assignments)]
+            fn pack_bits(&self, bits: &mut [::sand::StdLogic]) {
+                let mut low = 0;
+                #(#packs)*
+            }
+
+            #[allow(unused_variables, unused_mut, unused_assignments)]
+            fn unpack_bits(bits: &[::sand::StdLogic]) -> ::std::option::Option<Self> {
+                let mut low = 0;
+                #(#unpacks)*
+                ::std::option::Option::Some(#constructor)
+            }
+        }
+    }
+}
+
+/// Returns what a field type is if it has no fixed size.
+fn unsized_type(ty: &Type) -> Option<&'static str> {
+    match ty {
+        Type::Path(path) => {
+            let ident = &path.path.segments.last()?.ident;
+            if ident == "Vec"
+                || ident == "Box"
+                || ident == "String"
+                || ident == "Rc"
+                || ident == "Arc"
+            {
+                Some("heap allocated")
+            } else {
+                None
+            }
+        }
+        Type::Slice(_) => Some("a slice"),
+        Type::Reference(_) | Type::Ptr(_) => Some("a pointer"),
+        Type::TraitObject(_) | Type::ImplTrait(_) => Some("a trait object"),
+        Type::Paren(paren) => unsized_type(&paren.elem),
+        Type::Group(group) => unsized_type(&group.elem),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derive_signal_test() {
+        let generated = derive_signal(quote! {
+            struct Header {
+                valid: bool,
+                address: u8,
+            }
+        });
+        assert_eq!(generated.to_string(), quote!(
+            impl ::sand::trace::Trace for Header {
+                const WIDTH: usize = 0 + <bool as ::sand::trace::Trace>::WIDTH + <u8 as ::sand::trace::Trace>::WIDTH;
+
+                fn trace_bits(&self, bits: &mut ::std::string::String) {
+                    ::sand::trace::Trace::trace_bits(&self.valid, bits);
+                    ::sand::trace::Trace::trace_bits(&self.address, bits);
+                }
+
+                fn trace_vars(name: &str, vars: &mut ::std::vec::Vec<(::std::string::String, usize)>) {
+                    <bool as ::sand::trace::Trace>::trace_vars(&::std::format!("{}.{}", name, "valid"), vars);
+                    <u8 as ::sand::trace::Trace>::trace_vars(&::std::format!("{}.{}", name, "address"), vars);
+                }
+            }
+
+            impl ::sand::Signal for Header {
+                #[allow(unused_variables, unused_mut, unused_assignments)]
+                fn pack_bits(&self, bits: &mut [::sand::StdLogic]) {
+                    let mut low = 0;
+                    let high = low + <u8 as ::sand::trace::Trace>::WIDTH;
+                    ::sand::Signal::pack_bits(&self.address, &mut bits[low..high]);
+                    low = high;
+                    let high = low + <bool as ::sand::trace::Trace>::WIDTH;
+                    ::sand::Signal::pack_bits(&self.valid, &mut bits[low..high]);
+                    low = high;
+                }
+
+                #[allow(unused_variables, unused_mut, unused_assignments)]
+                fn unpack_bits(bits: &[::sand::StdLogic]) -> ::std::option::Option<Self> {
+                    let mut low = 0;
+                    let high = low + <u8 as ::sand::trace::Trace>::WIDTH;
+                    let field_1 = <u8 as ::sand::Signal>::unpack_bits(&bits[low..high])?;
+                    low = high;
+                    let high = low + <bool as ::sand::trace::Trace>::WIDTH;
+                    let field_0 = <bool as ::sand::Signal>::unpack_bits(&bits[low..high])?;
+                    low = high;
+                    ::std::option::Option::Some(Header { valid: field_0, address: field_1 })
+                }
+            }
+        ).to_string());
+    }
+
+    #[test]
+    fn derive_signal_should_error_for_unsized_field() {
+        let generated = derive_signal(quote! {
+            struct Packet {
+                id: u32,
+                payload: Vec<u32>,
+            }
+        });
+        assert_eq!(generated.to_string(), quote!(
+            compile_error!("The field `payload` is heap allocated which has no fixed size, please use a fixed size type such as an array.");
+        ).to_string());
+    }
+}
@@ -0,0 +1,167 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream, Result};
+use syn::spanned::Spanned;
+use syn::{Error, Ident, ItemFn, LitStr, Token};
+
+/// Arguments of the `#[sand::test]` attribute, which is the optional time limit.
+struct Args {
+    until: Option<LitStr>,
+}
+
+/// Parse a time limit as `sand::SimTime` does, such as `"1 us"`, to nanoseconds.
+fn nanos(until: &LitStr) -> Result<u64> {
+    let value = until.value();
+    let time = value.trim();
+    let split = time
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(time.len());
+    let (number, unit) = time.split_at(split);
+    let number: u64 = number.parse().map_err(|_| {
+        Error::new(
+            until.span(),
+            format!("'{}' does not start with an integer.", time),
+        )
+    })?;
+    let scale: u64 = match unit.trim() {
+        "ns" => 1,
+        "us" => 1_000,
+        "ms" => 1_000_000,
+        "s" => 1_000_000_000,
+        unit => {
+            let message = format!("'{}' is not a time unit, expected ns, us, ms or s.", unit);
+            return Err(Error::new(until.span(), message));
+        }
+    };
+    number
+        .checked_mul(scale)
+        .ok_or_else(|| Error::new(until.span(), "The time limit is too large."))
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> Result<Self> {
+        if input.is_empty() {
+            return Ok(Args { until: None });
+        }
+        let name: Ident = input.parse()?;
+        if name != "until" {
+            return Err(Error::new(name.span(), "Expected `until = \"<time>\"`."));
+        }
+        input.parse::<Token![=]>()?;
+        let until = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+        Ok(Args { until: Some(until) })
+    }
+}
+
+/// Turn an `async fn` without arguments into a test running it as a simulation process.
+pub fn test(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args: Args = match syn::parse2(args) {
+        Ok(args) => args,
+        Err(err) => return err.to_compile_error(),
+    };
+    let item: ItemFn = match syn::parse2(input) {
+        Ok(item) => item,
+        Err(err) => return err.to_compile_error(),
+    };
+    let sig = &item.sig;
+    if sig.asyncness.is_none() {
+        return Error::new(sig.fn_token.span, "A testbench must be an `async fn`.")
+            .to_compile_error();
+    }
+    if !sig.inputs.is_empty() {
+        return Error::new(sig.inputs.span(), "A testbench takes no arguments.").to_compile_error();
+    }
+    if !sig.generics.params.is_empty() {
+        return Error::new(sig.generics.span(), "A testbench cannot be generic.")
+            .to_compile_error();
+    }
+
+    let attrs = &item.attrs;
+    let vis = &item.vis;
+    let name = &sig.ident;
+    let path = name.to_string();
+    let until = match args.until.as_ref().map(nanos).transpose() {
+        Ok(Some(nanos)) => quote!(::core::option::Option::Some(::sand::SimTime::from_duration(
+            ::core::time::Duration::from_nanos(#nanos)
+        ))),
+        Ok(None) => quote!(::core::option::Option::None),
+        Err(err) => return err.to_compile_error(),
+    };
+    let block = &item.block;
+    quote! {
+        #[::core::prelude::v1::test]
+        #(#attrs)*
+        #vis fn #name() {
+            ::sand::testbench::run(#path, #until, async move #block);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_test() {
+        let generated = test(
+            quote!(until = "1 us"),
+            quote! {
+                async fn test_counter() {
+                    wait_for(10.ns()).await;
+                }
+            },
+        );
+        assert_eq!(
+            generated.to_string(),
+            quote! {
+                #[::core::prelude::v1::test]
+                fn test_counter() {
+                    ::sand::testbench::run(
+                        "test_counter",
+                        ::core::option::Option::Some(::sand::SimTime::from_duration(
+                            ::core::time::Duration::from_nanos(1000u64)
+                        )),
+                        async move {
+                            wait_for(10.ns()).await;
+                        }
+                    );
+                }
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn test_test_not_async() {
+        let generated = test(
+            quote!(),
+            quote!(
+                fn test_counter() {}
+            ),
+        )
+        .to_string();
+        assert!(generated.contains("A testbench must be an `async fn`."));
+        let generated = test(
+            quote!(),
+            quote!(
+                async fn test_counter(n: u8) {}
+            ),
+        )
+        .to_string();
+        assert!(generated.contains("A testbench takes no arguments."));
+    }
+
+    #[test]
+    fn test_test_until() {
+        let testbench = quote!(
+            async fn test_counter() {}
+        );
+        let generated = test(quote!(until = "10 fs"), testbench.clone()).to_string();
+        assert!(generated.contains("'fs' is not a time unit, expected ns, us, ms or s."));
+        let generated = test(quote!(until = "ns"), testbench.clone()).to_string();
+        assert!(generated.contains("'ns' does not start with an integer."));
+        let generated = test(quote!(until = "100000000000 s"), testbench).to_string();
+        assert!(generated.contains("The time limit is too large."));
+    }
+}
@@ -1,4 +1,4 @@
-use syn::{custom_punctuation};
+use syn::{custom_punctuation, Token};
 use syn::parse::{Parse, ParseStream};
 
 custom_punctuation!(In, <-);
@@ -11,18 +11,26 @@ pub(crate) enum Way {
     In,
     /// `->`
     Out,
+    /// `<->`
+    InOut,
 }
 
 impl Parse for Way {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         match input.parse::<In>() {
             Ok(_) => {
-                Ok(Way::In)
-            }
-            Err(_) => {
-                input.parse::<Out>()?;
-                Ok(Way::Out)
+                // `<->` is lexed as `<-` followed by `>`.
+                if input.peek(Token![>]) {
+                    input.parse::<Token![>]>()?;
+                    Ok(Way::InOut)
+                } else {
+                    Ok(Way::In)
+                }
             }
+            Err(_) => match input.parse::<Out>() {
+                Ok(_) => Ok(Way::Out),
+                Err(err) => Err(syn::Error::new(err.span(), "expected `<-`, `->` or `<->`")),
+            },
         }
     }
 }
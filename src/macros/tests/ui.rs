@@ -0,0 +1,16 @@
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.pass("../../examples/sorter/main.rs");
+    t.pass("tests/ui/inout.rs");
+    t.compile_fail("tests/ui/out_on_resolved.rs");
+    t.compile_fail("tests/ui/bad_connection.rs");
+    t.pass("tests/ui/entity.rs");
+    t.compile_fail("tests/ui/entity_not_synthesizable.rs");
+    t.compile_fail("tests/ui/entity_clock.rs");
+    t.compile_fail("tests/ui/entity_lowering.rs");
+    t.compile_fail("tests/ui/entity_constructs.rs");
+    t.compile_fail("tests/ui/signal_unsized.rs");
+    t.compile_fail("tests/ui/testbench_not_async.rs");
+    t.compile_fail("tests/ui/testbench_until.rs");
+}
@@ -0,0 +1,7 @@
+use sand::connections;
+
+fn main() {
+    let _sim = connections! {
+        producer.data => data;
+    };
+}
@@ -0,0 +1,47 @@
+// Every entity is lowered, so the warning of an entity without LLHD IR fails the test.
+#![deny(deprecated)]
+
+use sand::entity;
+
+#[entity]
+fn adder(a: u8, b: u8) -> u8 {
+    a + b
+}
+
+#[entity(clk)]
+fn counter(clk: bool, count: u8, enable: bool) -> (u8, bool) {
+    let next = if enable { count + 1 } else { count };
+    (next, next == 0)
+}
+
+#[entity]
+fn parity(a: u8) -> bool {
+    let mut parity = false;
+    for i in 0..8 {
+        parity ^= (a >> i) & 1 == 1;
+    }
+    parity
+}
+
+#[derive(Clone, Copy)]
+struct Accumulator {
+    total: u16,
+}
+
+impl Accumulator {
+    #[entity]
+    fn add(self, value: u16) -> Self {
+        Accumulator { total: self.total + value }
+    }
+}
+
+fn main() {
+    assert_eq!(3, adder(1, 2));
+    assert_eq!((255, false), counter(true, 254, true));
+    assert!(parity(0b1011));
+    assert!(!parity(0b1001));
+    assert_eq!(5, Accumulator { total: 2 }.add(3).total);
+    assert_eq!("adder", ADDER_ENTITY.name);
+    let module = sand::synth::module(&[ADDER_ENTITY, COUNTER_ENTITY, PARITY_ENTITY]).unwrap();
+    assert_eq!(3, module.entities().count());
+}
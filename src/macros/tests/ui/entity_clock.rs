@@ -0,0 +1,13 @@
+use sand::entity;
+
+#[entity(clk)]
+fn unknown_clock(a: u8) -> u8 {
+    a
+}
+
+#[entity(clk)]
+fn wide_clock(clk: u8) -> u8 {
+    clk
+}
+
+fn main() {}
@@ -0,0 +1,56 @@
+use sand::entity;
+
+#[entity]
+fn heap(a: Vec<u8>, b: Box<u8>) -> String {
+    String::new()
+}
+
+#[entity]
+fn forever(a: u8) -> u8 {
+    loop {
+        return a;
+    }
+}
+
+#[entity]
+fn countdown(a: u8) -> u8 {
+    let mut b = a;
+    while b > 0 {
+        b = b - 1;
+    }
+    b
+}
+
+#[entity]
+fn factorial(n: u8) -> u8 {
+    if n == 0 { 1 } else { n * factorial(n - 1) }
+}
+
+#[entity]
+async fn later(a: u8) -> u8 {
+    async { a }.await
+}
+
+#[entity]
+fn object(a: &dyn Fn(u8) -> u8) -> u8 {
+    a(0)
+}
+
+#[entity]
+fn pointer(a: *const u8) -> bool {
+    a.is_null()
+}
+
+#[entity]
+fn closure(a: u8) -> u8 {
+    let by_ref = |x: u8| -> u8 { x + a };
+    let by_move = move |x: u8| -> u8 { x + a };
+    by_ref(1) + by_move(2)
+}
+
+#[entity]
+fn float(a: f32) -> u8 {
+    (a * 0.5) as u8
+}
+
+fn main() {}
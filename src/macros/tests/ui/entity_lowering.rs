@@ -0,0 +1,25 @@
+// The entities which cannot be lowered are only warned about, the warnings are denied to check them.
+#![deny(deprecated)]
+
+use sand::entity;
+
+#[entity]
+fn call(a: u8) -> u32 {
+    a.count_ones()
+}
+
+#[entity]
+fn guard(a: u8) -> u8 {
+    match a {
+        0 => 1,
+        x if x > 4 => 2,
+        _ => 3,
+    }
+}
+
+#[entity]
+fn unsized_int(a: usize) -> usize {
+    a
+}
+
+fn main() {}
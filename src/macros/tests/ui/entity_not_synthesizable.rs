@@ -0,0 +1,19 @@
+use sand::entity;
+
+#[entity]
+fn no_output(a: u8) {}
+
+#[entity]
+fn empty_output(a: u8) -> () {}
+
+#[entity]
+fn deref(a: &u8) -> u8 {
+    *a
+}
+
+#[entity]
+fn not_copy(a: String) -> usize {
+    a.len()
+}
+
+fn main() {}
@@ -0,0 +1,56 @@
+use sand::{connections, ports, Read, StdLogic, Write};
+use sand::hierarchy::Kind;
+
+mod master {
+    use super::*;
+
+    ports! {
+        sda <-> StdLogic
+    }
+
+    pub(crate) async fn process(ports: &mut Ports) {
+        ports.sda.nb_write(StdLogic::Zero).unwrap();
+        sand::wait().await.unwrap();
+        ports.sda.release();
+    }
+}
+
+mod slave {
+    use super::*;
+
+    ports! {
+        sda <-> StdLogic
+    }
+
+    pub(crate) async fn process(ports: &mut Ports) {
+        ports.sda.nb_write(StdLogic::H).unwrap();
+        sand::wait().await.unwrap();
+        assert_eq!(Some(StdLogic::Zero), ports.sda.nb_read().ok());
+    }
+}
+
+mod monitor {
+    use super::*;
+
+    ports! {
+        sda <- StdLogic
+    }
+
+    pub(crate) async fn process(ports: &mut Ports) {
+        assert_eq!(Some(StdLogic::Zero), ports.sda.b_read().await.ok());
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let mut sim = connections! {
+        master.sda <-> sda;
+        slave.sda <-> sda;
+        monitor.sda <- sda;
+    };
+    let hierarchy = sim.hierarchy();
+    assert_eq!(Some(Kind::Signal), hierarchy.find("top.sda").map(|node| node.kind()));
+    assert_eq!(Some(Kind::Port), hierarchy.find("top.master.sda").map(|node| node.kind()));
+    assert_eq!(Some(Kind::Process), hierarchy.find("top.monitor.process").map(|node| node.kind()));
+    sim.run().await;
+}
@@ -0,0 +1,28 @@
+use sand::{connections, ports, StdLogic};
+
+mod master {
+    use super::*;
+
+    ports! {
+        sda <-> StdLogic
+    }
+
+    pub(crate) async fn process(_ports: &mut Ports) {}
+}
+
+mod pull_up {
+    use super::*;
+
+    ports! {
+        sda -> StdLogic
+    }
+
+    pub(crate) async fn process(_ports: &mut Ports) {}
+}
+
+fn main() {
+    let _sim = connections! {
+        master.sda <-> sda;
+        pull_up.sda -> sda;
+    };
+}
@@ -0,0 +1,16 @@
+use sand::Signal;
+
+#[derive(Clone, PartialEq, Signal)]
+struct Packet {
+    id: u32,
+    payload: Vec<u32>,
+    name: &'static str,
+}
+
+#[derive(Clone, PartialEq, Signal)]
+enum State {
+    Idle,
+    Busy,
+}
+
+fn main() {}
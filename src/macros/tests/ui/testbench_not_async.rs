@@ -0,0 +1,4 @@
+#[sand::test]
+fn test_counter() {}
+
+fn main() {}
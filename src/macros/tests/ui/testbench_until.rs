@@ -0,0 +1,4 @@
+#[sand::test(until = "10 fs")]
+async fn test_counter() {}
+
+fn main() {}
@@ -1,7 +1,11 @@
-//! This module contains the [In] and [Out] ports that connect to signals.
+//! This module contains the [In], [Out] and [InOut] ports that connect to signals.
 
-use crate::error::{BReadError, NBReadError};
+use crate::error::{BReadError, NBReadError, NBWriteError};
+use crate::event::Event;
+use crate::kernel::context;
+use crate::resolved::{Bus, Driver};
 use crate::signal::{Receiver, Sender};
+use crate::trace::Probe;
 use crate::{Read, Write};
 use async_trait::async_trait;
 
@@ -16,6 +20,7 @@ pub trait Wait {
 pub struct In<T: Clone + Send> {
     signal: Receiver<T>,
     value: Option<T>,
+    name: Option<String>,
 }
 
 impl<T: Clone + Send> In<T> {
@@ -24,8 +29,20 @@ impl<T: Clone + Send> In<T> {
         In {
             signal: rx,
             value: None,
+            name: None,
         }
     }
+
+    /// Give a hierarchical path to this port, such as `"top.cpu.clk"`.
+    pub fn named(mut self, path: &str) -> Self {
+        self.name = Some(path.to_string());
+        self
+    }
+
+    /// Returns the hierarchical path of this port, if it has one.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
 }
 
 #[async_trait]
@@ -51,50 +68,180 @@ impl<T: Clone + Send + PartialEq> Read<T> for In<T> {
     }
 
     async fn event(&mut self) {
-        self.b_read().await;
+        let _ = self.b_read().await;
+    }
+}
+
+impl<T: Clone + Send> Probe<T> for In<T> {
+    fn probe(&self) -> Receiver<T> {
+        self.signal.probe()
+    }
+}
+
+impl<T: Clone + Send + PartialEq + 'static> In<T> {
+    /// Returns the change event of the port, for the sensitivity list of a method process.
+    pub fn changed(&self) -> Event {
+        self.signal.changed()
+    }
+}
+
+impl In<bool> {
+    /// Returns the positive edge event of the port, for the sensitivity list of a method process.
+    pub fn pos(&self) -> Event {
+        self.signal.pos()
+    }
+
+    /// Returns the negative edge event of the port, for the sensitivity list of a method process.
+    pub fn neg(&self) -> Event {
+        self.signal.neg()
+    }
+
+    /// Suspend the process until a positive edge event is detected on the port.
+    ///
+    /// *This is only implemented for boolean value types.*
+    pub async fn posedge_event(&mut self) {
+        self.signal.posedge_event().await;
+    }
+
+    /// Suspend the process until a negative edge event is detected on the port.
+    ///
+    /// *This is only implemented for boolean value types.*
+    pub async fn negedge_event(&mut self) {
+        self.signal.negedge_event().await;
     }
 }
 
-// #[async_trait]
-// impl<T: Clone + Send> Wait for In<T> {
-//     async fn wait(&mut self) -> Result<(), ()> {
-//         self.value = match self.signal.nb_read() {
-//             Ok(val) => {}
-//             Err(_) => {}
-//         };
-//     }
-// }
+#[async_trait]
+impl<T: Clone + Send + PartialEq> Wait for In<T> {
+    /// Wait until next delta-cycle, then refresh the value held by the port.
+    async fn wait(&mut self) -> Result<(), ()> {
+        crate::wait().await?;
+        let _ = self.nb_read();
+        Ok(())
+    }
+}
 
 /// This is a port for outgoing signals.
 pub struct Out<T: Clone + Send> {
     signal: Sender<T>,
+    name: Option<String>,
 }
 
 impl<T: Clone + Send> Out<T> {
     /// Connect the signal sender to this port.
     pub fn connect(tx: Sender<T>) -> Self {
-        Out { signal: tx }
+        Out {
+            signal: tx,
+            name: None,
+        }
+    }
+
+    /// Give a hierarchical path to this port, such as `"top.cpu.data"`.
+    pub fn named(mut self, path: &str) -> Self {
+        self.name = Some(path.to_string());
+        self
+    }
+
+    /// Returns the hierarchical path of this port, if it has one.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+}
+
+impl<T: Clone + Send> Probe<T> for Out<T> {
+    fn probe(&self) -> Receiver<T> {
+        self.signal.probe()
     }
 }
 
 #[async_trait]
-impl<T: Clone + Send> Write<T> for Out<T> {
-    fn nb_write(&self, val: T) {
-        self.signal.nb_write(val);
+impl<T: Clone + Send + 'static> Write<T> for Out<T> {
+    fn nb_write(&self, val: T) -> Result<(), NBWriteError> {
+        self.signal.nb_write(val)
+    }
+}
+
+/// This is a port for bidirectional resolved signals.
+///
+/// The port reads the resolved value of the signal, but it can only write its own contribution,
+/// so it cannot override the other drivers of the signal. Driving a strong value against another
+/// strong driver is reported as a short circuit.
+pub struct InOut<T: Clone + Send> {
+    signal: Receiver<T>,
+    driver: Driver<T>,
+    name: String,
+}
+
+impl<T: Clone + Send + 'static> InOut<T> {
+    /// Connect a new driver of the resolved signal to this port.
+    ///
+    /// The name identifies the driver in short circuit reports, it is the hierarchical path of the
+    /// port.
+    pub fn connect(bus: &Bus<T>, name: &str) -> Self {
+        InOut {
+            signal: bus.subscribe(),
+            driver: bus.driver(name),
+            name: name.to_string(),
+        }
+    }
+
+    /// Returns the hierarchical path of this port.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Stop driving the signal, e.g. put a tri-state output in high impedance.
+    pub fn release(&self) {
+        self.driver.release();
+    }
+}
+
+impl<T: Clone + Send> Probe<T> for InOut<T> {
+    fn probe(&self) -> Receiver<T> {
+        self.signal.probe()
+    }
+}
+
+#[async_trait]
+impl<T: Clone + Send + PartialEq> Read<T> for InOut<T> {
+    fn nb_read(&mut self) -> Result<T, NBReadError> {
+        self.signal.nb_read()
+    }
+
+    /// The port drives the signal too, so it is never closed: a process waiting on it is idle
+    /// rather than in a deadlock.
+    async fn b_read(&mut self) -> Result<T, BReadError> {
+        let _idle = context::idle();
+        self.signal.b_read().await
+    }
+
+    async fn event(&mut self) {
+        let _ = self.b_read().await;
+    }
+}
+
+#[async_trait]
+impl<T: Clone + Send + 'static> Write<T> for InOut<T> {
+    /// Drive the contribution of this port.
+    fn nb_write(&self, val: T) -> Result<(), NBWriteError> {
+        self.driver.nb_write(val)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::logic::StdLogic;
+    use crate::resolved::resolved;
     use crate::signal::signal;
+    use crate::{wait_for, SchedulerMode, Simulator, TimeExt};
 
     #[tokio::test]
     async fn test_out_write() {
         let test_val = 42;
         let (tx, mut rx) = signal();
         let out = Out::connect(tx);
-        out.nb_write(test_val);
+        out.nb_write(test_val).unwrap();
         assert_eq!(test_val, rx.nb_read().unwrap_or(0));
     }
 
@@ -103,7 +250,82 @@ mod tests {
         let test_val = 42;
         let (tx, rx) = signal();
         let mut port_in = In::connect(rx);
-        tx.nb_write(test_val);
+        tx.nb_write(test_val).unwrap();
         assert_eq!(test_val, port_in.nb_read().unwrap_or(0));
     }
+
+    #[test]
+    fn test_port_names() {
+        let (tx, rx) = signal::<bool>();
+        assert_eq!(
+            Some("top.cpu.clk"),
+            In::connect(rx).named("top.cpu.clk").name()
+        );
+        assert_eq!(None, Out::connect(tx).name());
+        let (bus, _) = resolved::<StdLogic>();
+        assert_eq!("top.cpu.sda", InOut::connect(&bus, "top.cpu.sda").name());
+    }
+
+    #[tokio::test]
+    async fn test_in_wait() {
+        let (tx, rx) = signal();
+        let mut port_in = In::connect(rx);
+        assert!(port_in.wait().await.is_err());
+
+        let mut sim = Simulator::with_scheduler(SchedulerMode::Deterministic { seed: None });
+        sim.spawn(async move {
+            tx.nb_write(42).unwrap();
+            assert!(port_in.wait().await.is_ok());
+            assert_eq!(42, port_in.nb_read().unwrap_or(0));
+        });
+        sim.run().await;
+    }
+
+    #[tokio::test]
+    async fn test_inout_drive_release() {
+        let mut sim = Simulator::with_scheduler(SchedulerMode::Deterministic { seed: None });
+        let (sda, _) = resolved::<StdLogic>();
+        let pull_up = sda.driver("pull_up");
+        let mut controller = InOut::connect(&sda, "controller");
+        let mut target = InOut::connect(&sda, "target");
+        sim.spawn(async move {
+            pull_up.nb_write(StdLogic::H).unwrap();
+            controller.nb_write(StdLogic::Zero).unwrap();
+            assert_eq!(
+                StdLogic::Zero,
+                controller.b_read().await.unwrap_or(StdLogic::U)
+            );
+            controller.release();
+            assert_eq!(
+                StdLogic::H,
+                controller.b_read().await.unwrap_or(StdLogic::U)
+            );
+        });
+        sim.spawn(async move {
+            wait_for(10.ns()).await;
+            target.nb_write(StdLogic::Zero).unwrap();
+            crate::wait().await.unwrap_or(());
+            assert_eq!(StdLogic::Zero, target.nb_read().unwrap_or(StdLogic::U));
+        });
+        sim.run().await;
+    }
+
+    #[tokio::test]
+    async fn test_inout_idle_reader() {
+        let mut sim = Simulator::with_scheduler(SchedulerMode::Deterministic { seed: None });
+        let (sda, _) = crate::resolved::named::<StdLogic>("top.sda");
+        let controller = sda.driver("top.master.sda");
+        let mut target = InOut::connect(&sda, "top.slave.sda");
+        sim.spawn_named("top.master.process", async move {
+            for value in [StdLogic::Zero, StdLogic::One] {
+                controller.nb_write(value).unwrap();
+                wait_for(10.ns()).await;
+            }
+        });
+        // The port keeps driving the signal, so the reader waits forever without a deadlock.
+        sim.spawn_named("top.slave.process", async move {
+            while target.b_read().await.is_ok() {}
+        });
+        assert_eq!(20, sim.run().await.as_nanos());
+    }
 }
@@ -1,3 +1,76 @@
 //! This module contains the buffered signal.
+//!
+//! Unlike the basic signal, every write to a buffer triggers an event, even if the written value is
+//! the same as the previous one. This is useful to model strobes and handshakes.
 
-struct Buffer {}
+use crate::error::{BReadError, NBReadError};
+use crate::signal::{self, signal};
+use crate::Read;
+use async_trait::async_trait;
+
+/// The writing end of a buffer is the same as the one of the basic signal.
+pub use crate::signal::Sender;
+
+/// Receiver of a buffer.
+pub struct Receiver<T: Clone + Send> {
+    signal: signal::Receiver<T>,
+}
+
+#[async_trait]
+impl<T: Clone + Send + PartialEq> Read<T> for Receiver<T> {
+    fn nb_read(&mut self) -> Result<T, NBReadError> {
+        self.signal.nb_read()
+    }
+
+    /// Read the next value written on the buffer, even if it did not change.
+    async fn b_read(&mut self) -> Result<T, BReadError> {
+        self.signal.recv_write().await
+    }
+
+    /// Suspend the process until the buffer is written to.
+    async fn event(&mut self) {
+        let _ = self.b_read().await;
+    }
+}
+
+/// Contructs a buffer and returns the Sender and Receiver handles.
+pub fn buffer<T: Clone + Send>() -> (Sender<T>, Receiver<T>) {
+    let (tx, rx) = signal();
+    (tx, Receiver { signal: rx })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{wait_for, SchedulerMode, Simulator, TimeExt, Write};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_buffer_event_on_every_write() {
+        let mut sim = Simulator::with_scheduler(SchedulerMode::Deterministic { seed: None });
+        let (tx, mut rx) = buffer();
+        let mut signal_rx = tx.subscribe();
+        let events = Arc::new(AtomicUsize::new(0));
+        let count = events.clone();
+        sim.spawn(async move {
+            for _ in 0..3 {
+                tx.nb_write(true).unwrap();
+                wait_for(10.ns()).await;
+            }
+        });
+        sim.spawn(async move {
+            while rx.b_read().await.is_ok() {
+                assert!(rx.nb_read().unwrap_or(false));
+                count.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        sim.spawn(async move {
+            // The basic signal only sees the first write.
+            assert!(signal_rx.b_read().await.unwrap_or(false));
+            assert!(signal_rx.b_read().await.is_err());
+        });
+        sim.run().await;
+        assert_eq!(3, events.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,158 @@
+//! This module holds the clock generator.
+
+use crate::signal::{signal, Receiver, Sender};
+use crate::trace::Probe;
+use crate::{wait_for, Write};
+use std::time::Duration;
+
+/// A clock source driven by simulated time.
+///
+/// The clock holds its start level until the end of its phase, then toggles on every edge. It
+/// stays high for `duty_cycle * period` in every period.
+///
+/// The clock process returns as soon as no [Receiver] is connected to the clock anymore, so that
+/// the simulation can end. A traced clock always has a Receiver, so it runs until the end of the
+/// simulation.
+pub struct Clock {
+    tx: Sender<bool>,
+    period: Duration,
+    duty_cycle: f64,
+    phase: Duration,
+    start_level: bool,
+}
+
+impl Clock {
+    /// Construct a clock with a 50% duty cycle whose first positive edge is at the start of the
+    /// simulation.
+    pub fn new(period: Duration) -> Self {
+        assert!(!period.is_zero(), "The clock period must not be zero.");
+        let (tx, _) = signal();
+        Clock {
+            tx,
+            period,
+            duty_cycle: 0.5,
+            phase: Duration::ZERO,
+            start_level: false,
+        }
+    }
+
+    /// Set the fraction of the period during which the clock is high.
+    ///
+    /// # Panics
+    /// The duty cycle must be strictly between 0 and 1.
+    pub fn duty_cycle(mut self, duty_cycle: f64) -> Self {
+        assert!(
+            duty_cycle > 0.0 && duty_cycle < 1.0,
+            "The clock duty cycle must be strictly between 0 and 1."
+        );
+        self.duty_cycle = duty_cycle;
+        self
+    }
+
+    /// Set the delay before the first edge of the clock.
+    pub fn phase(mut self, phase: Duration) -> Self {
+        self.phase = phase;
+        self
+    }
+
+    /// Set the level of the clock before its first edge.
+    pub fn start_level(mut self, start_level: bool) -> Self {
+        self.start_level = start_level;
+        self
+    }
+
+    /// Create a new Receiver connected to this clock.
+    pub fn subscribe(&self) -> Receiver<bool> {
+        self.tx.subscribe()
+    }
+
+    /// The clock process, to spawn in a [`Simulator`](crate::Simulator).
+    pub async fn process(self) {
+        let high = self.period.mul_f64(self.duty_cycle);
+        let low = self.period - high;
+
+        let mut level = self.start_level;
+        if self.tx.nb_write(level).is_err() {
+            return;
+        }
+        wait_for(self.phase).await;
+
+        loop {
+            level = !level;
+            if self.tx.nb_write(level).is_err() {
+                return;
+            }
+            wait_for(if level { high } else { low }).await;
+        }
+    }
+}
+
+impl Probe<bool> for Clock {
+    fn probe(&self) -> Receiver<bool> {
+        self.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ports::In;
+    use crate::{now, SchedulerMode, SimTime, Simulator, TimeExt};
+    use std::sync::{Arc, Mutex};
+
+    /// Returns the times of the first edges of the clock.
+    async fn edges(clock: Clock, posedges: usize) -> (Vec<u128>, Vec<u128>) {
+        let mut sim = Simulator::with_scheduler(SchedulerMode::Deterministic { seed: None });
+        let times = Arc::new(Mutex::new((Vec::new(), Vec::new())));
+        let mut clk = In::connect(clock.subscribe());
+        let mut clk_n = clock.subscribe();
+        let (pos, neg) = (times.clone(), times.clone());
+        sim.spawn(async move {
+            for _ in 0..posedges {
+                clk.posedge_event().await;
+                pos.lock().unwrap().0.push(now().as_nanos());
+            }
+        });
+        sim.spawn(async move {
+            for _ in 0..posedges {
+                clk_n.negedge_event().await;
+                neg.lock().unwrap().1.push(now().as_nanos());
+            }
+        });
+        sim.spawn(clock.process());
+        sim.run().await;
+        let times = times.lock().unwrap().clone();
+        times
+    }
+
+    #[tokio::test]
+    async fn test_clock_default() {
+        let (posedges, negedges) = edges(Clock::new(10.ns()), 3).await;
+        assert_eq!(vec![0, 10, 20], posedges);
+        assert_eq!(vec![5, 15, 25], negedges);
+    }
+
+    #[tokio::test]
+    async fn test_clock_duty_cycle_phase_level() {
+        let clock = Clock::new(8.ns())
+            .duty_cycle(0.25)
+            .phase(3.ns())
+            .start_level(true);
+        let (posedges, negedges) = edges(clock, 2).await;
+        assert_eq!(vec![9, 17], posedges);
+        assert_eq!(vec![3, 11], negedges);
+    }
+
+    #[tokio::test]
+    async fn test_clock_stops_without_receivers() {
+        let mut sim = Simulator::new();
+        let clock = Clock::new(10.ns());
+        let mut clk = clock.subscribe();
+        sim.spawn(async move {
+            clk.posedge_event().await;
+            clk.posedge_event().await;
+        });
+        sim.spawn(clock.process());
+        assert_eq!(SimTime::ZERO + 15.ns(), sim.run().await);
+    }
+}
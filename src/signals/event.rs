@@ -0,0 +1,303 @@
+//! This module holds the events of the static sensitivity lists of method processes, and the
+//! [`wait_any!`](crate::wait_any) and [`wait_all!`](crate::wait_all) combinators of the events
+//! awaited by a process.
+
+use crate::signal::Receiver;
+use crate::Read;
+use futures::future::{poll_fn, select_all, BoxFuture, FutureExt};
+use std::future::Future;
+use std::task::Poll;
+
+#[doc(hidden)]
+pub use futures::join as __join;
+
+/// An event on a signal, which triggers a method process.
+///
+/// The events are built from the signals or the ports read by the method, with `changed()` for
+/// any change of the value, and `pos()` or `neg()` for the edges of a boolean signal. An event
+/// reads its own copy of the signal, so the method still reads the signal itself.
+pub struct Event {
+    source: Box<dyn Source>,
+}
+
+/// Signal watched by an event.
+trait Source: Send {
+    /// Wait until the event is triggered. Returns false once the signal is closed.
+    fn triggered(&mut self) -> BoxFuture<'_, bool>;
+}
+
+struct Changed<T: Clone + Send>(Receiver<T>);
+
+impl<T: Clone + Send + PartialEq + 'static> Source for Changed<T> {
+    fn triggered(&mut self) -> BoxFuture<'_, bool> {
+        async move { self.0.b_read().await.is_ok() }.boxed()
+    }
+}
+
+struct Edge {
+    signal: Receiver<bool>,
+    level: bool,
+}
+
+impl Source for Edge {
+    fn triggered(&mut self) -> BoxFuture<'_, bool> {
+        async move { self.signal.edge(self.level).await.is_ok() }.boxed()
+    }
+}
+
+impl Event {
+    /// Event triggered when the value of the signal changes.
+    pub(crate) fn changed<T: Clone + Send + PartialEq + 'static>(signal: Receiver<T>) -> Self {
+        Event {
+            source: Box::new(Changed(signal)),
+        }
+    }
+
+    /// Event triggered when the signal changes from the opposite of `level` to `level`.
+    pub(crate) fn edge(signal: Receiver<bool>, level: bool) -> Self {
+        Event {
+            source: Box::new(Edge { signal, level }),
+        }
+    }
+}
+
+/// Wait until any of the events is triggered.
+///
+/// The other events triggered in the same *delta-cycle* are consumed too, so that they do not
+/// trigger again. The events of closed signals are removed, and this returns false once all of
+/// them are.
+async fn triggered(events: &mut Vec<Event>) -> bool {
+    loop {
+        if events.is_empty() {
+            return false;
+        }
+        let (triggered, index) = {
+            let waits = events.iter_mut().map(|event| event.source.triggered());
+            let (triggered, index, _) = select_all(waits).await;
+            (triggered, index)
+        };
+        if triggered {
+            break;
+        }
+        events.remove(index);
+    }
+    events.retain_mut(|event| event.source.triggered().now_or_never() != Some(false));
+    true
+}
+
+/// Run `method` at the start of the simulation, then every time one of the events of its static
+/// sensitivity is triggered, as a SystemC `SC_METHOD`.
+///
+/// The method runs to completion in a single *delta-cycle*, so it cannot wait. This returns once
+/// every signal of the sensitivity is closed. Await it in a process, such as the `process` of a
+/// module connected with [`connections!`](crate::connections), or spawn it with
+/// [`Simulator::spawn_method`](crate::Simulator::spawn_method).
+pub async fn method<I, F>(sensitivity: I, mut method: F)
+where
+    I: IntoIterator<Item = Event>,
+    F: FnMut(),
+{
+    let mut events: Vec<Event> = sensitivity.into_iter().collect();
+    loop {
+        method();
+        // A method waiting for its sensitivity is idle, not in a deadlock.
+        let _idle = crate::kernel::context::idle();
+        if !triggered(&mut events).await {
+            return;
+        }
+    }
+}
+
+/// Suspend the process until any of the events is triggered, and evaluate to the index of the
+/// event in the list.
+///
+/// An event is any future, such as `rx.event()`, `clk.posedge_event()` or `wait_for(50.ns())`
+/// for a timeout. When several events are triggered in the same *delta-cycle*, the first one in
+/// the list is reported. The other events are dropped without being consumed, so awaiting one of
+/// them again returns in the same *delta-cycle*. The outputs of the events are discarded, the
+/// value of a signal which triggered is read back with `nb_read()`.
+///
+/// This must be used in a simulation process.
+///
+/// ```ignore
+/// match wait_any!(ports.pro_to_ic.event(), wait_for(50.ns())) {
+///     0 => { /* A packet arrived. */ }
+///     _ => { /* Timeout. */ }
+/// }
+/// ```
+#[macro_export]
+macro_rules! wait_any {
+    ($($event:expr),+ $(,)?) => {
+        $crate::event::first(::std::vec![$($crate::event::boxed($event)),+]).await
+    };
+}
+
+/// Suspend the process until all of the events are triggered, and evaluate to the tuple of their
+/// outputs.
+///
+/// The events are awaited together, so the events triggered before the last one are not missed,
+/// and a timeout starts when `wait_all!` is reached.
+///
+/// This must be used in a simulation process.
+///
+/// ```ignore
+/// let (packet, _) = wait_all!(ports.copro1_to_ic.b_read(), ports.copro1_to_ic_ready.posedge_event());
+/// ```
+#[macro_export]
+macro_rules! wait_all {
+    ($($event:expr),+ $(,)?) => {
+        $crate::event::__join!($($event),+)
+    };
+}
+
+/// Box an event of [`wait_any!`](crate::wait_any), discarding its output.
+#[doc(hidden)]
+pub fn boxed<'a, F>(event: F) -> BoxFuture<'a, ()>
+where
+    F: Future + Send + 'a,
+{
+    event.map(|_| ()).boxed()
+}
+
+/// Wait until any of the events is ready, and returns the index of the first one ready.
+#[doc(hidden)]
+pub async fn first(mut events: Vec<BoxFuture<'_, ()>>) -> usize {
+    poll_fn(|cx| {
+        for (index, event) in events.iter_mut().enumerate() {
+            if event.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(index);
+            }
+        }
+        Poll::Pending
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ports::{In, Out};
+    use crate::signal::signal;
+    use crate::{wait_for, Read, SchedulerMode, Simulator, TimeExt, Write};
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn test_method() {
+        let mut sim = Simulator::with_scheduler(SchedulerMode::Deterministic { seed: None });
+        let (clk, clk_rx) = signal::<bool>();
+        let (rst, rst_rx) = signal::<bool>();
+        let runs = Arc::new(Mutex::new(Vec::new()));
+        let run = runs.clone();
+        sim.spawn_method("top.counter", [clk_rx.pos(), rst_rx.changed()], move || {
+            run.lock().unwrap().push(crate::now().as_nanos());
+        });
+        sim.spawn(async move {
+            clk.nb_write(false).unwrap();
+            rst.nb_write(true).unwrap();
+            wait_for(10.ns()).await;
+            // Both events in the same delta-cycle run the method once.
+            clk.nb_write(true).unwrap();
+            rst.nb_write(false).unwrap();
+            wait_for(10.ns()).await;
+            // A negative edge is not in the sensitivity.
+            clk.nb_write(false).unwrap();
+            wait_for(10.ns()).await;
+            clk.nb_write(true).unwrap();
+            wait_for(10.ns()).await;
+        });
+        sim.run().await;
+        assert_eq!(vec![0, 0, 10, 30], *runs.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_method_ports() {
+        let mut sim = Simulator::with_scheduler(SchedulerMode::Deterministic { seed: None });
+        let (a, a_rx) = signal::<u8>();
+        let (b, b_rx) = signal::<u8>();
+        let (sum, mut sum_rx) = signal::<u8>();
+        let mut a_in = In::connect(a_rx);
+        let mut b_in = In::connect(b_rx);
+        let sum_out = Out::connect(sum);
+        sim.spawn(async move {
+            let sensitivity = [a_in.changed(), b_in.changed()];
+            method(sensitivity, || {
+                if let (Ok(a), Ok(b)) = (a_in.nb_read(), b_in.nb_read()) {
+                    sum_out.nb_write(a + b).unwrap();
+                }
+            })
+            .await;
+        });
+        sim.spawn(async move {
+            a.nb_write(1).unwrap();
+            b.nb_write(2).unwrap();
+            wait_for(10.ns()).await;
+            assert_eq!(Ok(3), sum_rx.nb_read());
+            b.nb_write(5).unwrap();
+            wait_for(10.ns()).await;
+            assert_eq!(Ok(6), sum_rx.nb_read());
+        });
+        sim.run().await;
+    }
+
+    #[crate::test(until = "1 us")]
+    async fn test_wait_any() {
+        let (tx, mut rx) = signal::<u8>();
+        let (ready, mut ready_rx) = signal::<bool>();
+        crate::spawn(async move {
+            wait_for(10.ns()).await;
+            tx.nb_write(1).unwrap();
+            ready.nb_write(true).unwrap();
+            wait_for(100.ns()).await;
+        });
+        assert_eq!(1, crate::wait_any!(rx.event(), wait_for(5.ns())));
+        assert_eq!(5, crate::now().as_nanos());
+        // The first event of the list is reported when several are triggered together.
+        assert_eq!(
+            0,
+            crate::wait_any!(ready_rx.event(), rx.event(), wait_for(50.ns()))
+        );
+        assert_eq!(10, crate::now().as_nanos());
+        // The value of the other event was not consumed.
+        assert_eq!(0, crate::wait_any!(rx.event(), wait_for(50.ns())));
+        assert_eq!(10, crate::now().as_nanos());
+        assert_eq!(Ok(1), rx.nb_read());
+    }
+
+    #[crate::test(until = "1 us")]
+    async fn test_wait_all() {
+        let (tx, mut rx) = signal::<u8>();
+        let (ready, mut ready_rx) = signal::<bool>();
+        crate::spawn(async move {
+            wait_for(10.ns()).await;
+            tx.nb_write(1).unwrap();
+            wait_for(10.ns()).await;
+            ready.nb_write(true).unwrap();
+            wait_for(100.ns()).await;
+        });
+        let (value, ready, ()) =
+            crate::wait_all!(rx.b_read(), ready_rx.b_read(), wait_for(15.ns()));
+        assert_eq!((Ok(1), Ok(true)), (value, ready));
+        assert_eq!(20, crate::now().as_nanos());
+    }
+
+    #[tokio::test]
+    async fn test_wait_any_timeout_cancelled() {
+        for mode in [
+            SchedulerMode::Threaded,
+            SchedulerMode::Deterministic { seed: None },
+        ] {
+            let mut sim = Simulator::with_scheduler(mode);
+            let (tx, mut rx) = signal::<u8>();
+            sim.spawn(async move {
+                wait_for(10.ns()).await;
+                tx.nb_write(1).unwrap();
+            });
+            sim.spawn(async move {
+                assert_eq!(0, crate::wait_any!(rx.event(), wait_for(1000.ns())));
+            });
+            // The timeout which lost is removed from the time wheel.
+            assert_eq!(10, sim.run().await.as_nanos());
+            assert_eq!(1, sim.statistics().time_steps);
+        }
+    }
+}
@@ -1,3 +1,358 @@
 //! This module holds the fifo signal.
+//!
+//! Like the basic signal, the values written to a fifo during an evaluation phase can only be read
+//! at the next *delta-cycle*. Likewise, the room freed by a read is only available to the writer
+//! at the next *delta-cycle*.
 
-struct Fifo {}
+use crate::error::{BReadError, BWriteError, NBReadError, NBWriteError};
+use crate::kernel::{context, Update};
+use crate::Read;
+use async_trait::async_trait;
+use futures::future::poll_fn;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Poll, Waker};
+
+struct State<T> {
+    capacity: usize,
+    /// Values that can be read in the current *delta-cycle*.
+    readable: VecDeque<T>,
+    /// Values written in the current *delta-cycle*.
+    written: VecDeque<T>,
+    /// Number of values read in the current *delta-cycle*.
+    read: usize,
+    senders: usize,
+    receiver: bool,
+    update_requested: bool,
+    read_wakers: Vec<Waker>,
+    write_wakers: Vec<Waker>,
+}
+
+impl<T> State<T> {
+    fn num_free(&self) -> usize {
+        self.capacity - self.readable.len() - self.written.len() - self.read
+    }
+
+    fn is_closed(&self) -> bool {
+        self.senders == 0 && self.readable.is_empty() && self.written.is_empty()
+    }
+}
+
+struct Inner<T> {
+    state: Mutex<State<T>>,
+}
+
+impl<T: Send + 'static> Inner<T> {
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().expect("Fifo state poisoned.")
+    }
+
+    /// Request an update of the fifo at the end of the evaluation phase, if one is not already
+    /// requested.
+    fn request_update(self: &Arc<Self>, state: &mut State<T>, kernel: context::Handle) {
+        if !state.update_requested {
+            state.update_requested = true;
+            kernel
+                .shared
+                .request_update(Box::new(FifoUpdate(self.clone())));
+        }
+    }
+
+    fn write(self: &Arc<Self>, mut state: MutexGuard<'_, State<T>>, val: T) {
+        match context::current() {
+            Some(kernel) => {
+                state.written.push_back(val);
+                self.request_update(&mut state, kernel);
+            }
+            None => {
+                // Outside of a simulation there are no delta-cycles, so the value is readable now.
+                state.readable.push_back(val);
+                let wakers = std::mem::take(&mut state.read_wakers);
+                drop(state);
+                wakers.into_iter().for_each(Waker::wake);
+            }
+        }
+    }
+
+    fn read(self: &Arc<Self>) -> Result<T, NBReadError> {
+        let mut state = self.lock();
+        match state.readable.pop_front() {
+            Some(val) => {
+                match context::current() {
+                    Some(kernel) => {
+                        state.read += 1;
+                        self.request_update(&mut state, kernel);
+                    }
+                    None => {
+                        let wakers = std::mem::take(&mut state.write_wakers);
+                        drop(state);
+                        wakers.into_iter().for_each(Waker::wake);
+                    }
+                }
+                Ok(val)
+            }
+            None if state.is_closed() => Err(NBReadError::Closed),
+            None => Err(NBReadError::Empty),
+        }
+    }
+}
+
+/// Propagates the reads and writes of a *delta-cycle*.
+struct FifoUpdate<T>(Arc<Inner<T>>);
+
+impl<T: Send + 'static> Update for FifoUpdate<T> {
+    fn update(self: Box<Self>) {
+        let wakers = {
+            let mut state = self.0.lock();
+            state.update_requested = false;
+            let mut wakers = Vec::new();
+            if !state.written.is_empty() {
+                let written = std::mem::take(&mut state.written);
+                state.readable.extend(written);
+                wakers.append(&mut state.read_wakers);
+            }
+            if state.read > 0 {
+                state.read = 0;
+                wakers.append(&mut state.write_wakers);
+            }
+            wakers
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+/// Writing end of a fifo.
+pub struct Sender<T: Send + 'static> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T: Send + 'static> Sender<T> {
+    /// Write a value to the fifo if it has room for it.
+    ///
+    /// The possible error values are:
+    /// - [`NBWriteError::Full`]
+    /// - [`NBWriteError::Closed`]
+    pub fn nb_write(&self, val: T) -> Result<(), NBWriteError> {
+        let state = self.inner.lock();
+        if !state.receiver {
+            return Err(NBWriteError::Closed);
+        }
+        if state.num_free() == 0 {
+            return Err(NBWriteError::Full);
+        }
+        self.inner.write(state, val);
+        Ok(())
+    }
+
+    /// Write a value to the fifo, suspending the process until the fifo has room for it.
+    ///
+    /// The possible error values is:
+    /// - [`BWriteError::Closed`]
+    pub async fn b_write(&self, val: T) -> Result<(), BWriteError> {
+        let mut val = Some(val);
+        poll_fn(|cx| {
+            let mut state = self.inner.lock();
+            if !state.receiver {
+                return Poll::Ready(Err(BWriteError::Closed));
+            }
+            if state.num_free() == 0 {
+                state.write_wakers.push(cx.waker().clone());
+                return Poll::Pending;
+            }
+            self.inner
+                .write(state, val.take().expect("Fifo value already written."));
+            Poll::Ready(Ok(()))
+        })
+        .await
+    }
+
+    /// Returns the number of values that can be written in the current *delta-cycle*.
+    pub fn num_free(&self) -> usize {
+        self.inner.lock().num_free()
+    }
+}
+
+impl<T: Send + 'static> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.inner.lock().senders += 1;
+        Sender {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: Send + 'static> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut state = self.inner.lock();
+            state.senders -= 1;
+            if state.senders == 0 {
+                std::mem::take(&mut state.read_wakers)
+            } else {
+                Vec::new()
+            }
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+/// Reading end of a fifo.
+pub struct Receiver<T: Send + 'static> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T: Send + 'static> Receiver<T> {
+    /// Returns the number of values that can be read in the current *delta-cycle*.
+    pub fn num_available(&self) -> usize {
+        self.inner.lock().readable.len()
+    }
+}
+
+#[async_trait]
+impl<T: Send + 'static> Read<T> for Receiver<T> {
+    /// Read the oldest value in the fifo, if any.
+    fn nb_read(&mut self) -> Result<T, NBReadError> {
+        self.inner.read()
+    }
+
+    /// Read the oldest value in the fifo, suspending the process until there is one.
+    async fn b_read(&mut self) -> Result<T, BReadError> {
+        poll_fn(|cx| match self.inner.read() {
+            Ok(val) => Poll::Ready(Ok(val)),
+            Err(NBReadError::Closed) => Poll::Ready(Err(BReadError::Closed)),
+            Err(NBReadError::Empty) => {
+                self.inner.lock().read_wakers.push(cx.waker().clone());
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    /// Suspend the process until a value can be read from the fifo.
+    async fn event(&mut self) {
+        poll_fn(|cx| {
+            let mut state = self.inner.lock();
+            if !state.readable.is_empty() || state.is_closed() {
+                Poll::Ready(())
+            } else {
+                state.read_wakers.push(cx.waker().clone());
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+impl<T: Send + 'static> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut state = self.inner.lock();
+            state.receiver = false;
+            std::mem::take(&mut state.write_wakers)
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+/// Constructs a fifo holding up to `capacity` values and returns the Sender and Receiver handles.
+///
+/// # Panics
+/// The capacity must not be zero.
+pub fn fifo<T: Send + 'static>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "The fifo capacity must not be zero.");
+    let inner = Arc::new(Inner {
+        state: Mutex::new(State {
+            capacity,
+            readable: VecDeque::with_capacity(capacity),
+            written: VecDeque::new(),
+            read: 0,
+            senders: 1,
+            receiver: true,
+            update_requested: false,
+            read_wakers: Vec::new(),
+            write_wakers: Vec::new(),
+        }),
+    });
+    (
+        Sender {
+            inner: inner.clone(),
+        },
+        Receiver { inner },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{now, wait_for, SchedulerMode, Simulator, TimeExt};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_fifo_nb() {
+        let (tx, mut rx) = fifo(2);
+        assert!(matches!(rx.nb_read(), Err(NBReadError::Empty)));
+        assert!(tx.nb_write(1).is_ok());
+        assert!(tx.nb_write(2).is_ok());
+        assert!(matches!(tx.nb_write(3), Err(NBWriteError::Full)));
+        assert_eq!(2, rx.num_available());
+        assert_eq!(0, tx.num_free());
+        assert_eq!(1, rx.nb_read().unwrap_or(0));
+        assert_eq!(1, tx.num_free());
+        drop(tx);
+        assert_eq!(2, rx.nb_read().unwrap_or(0));
+        assert!(matches!(rx.nb_read(), Err(NBReadError::Closed)));
+    }
+
+    #[test]
+    fn test_fifo_closed_receiver() {
+        let (tx, rx) = fifo(1);
+        drop(rx);
+        assert!(matches!(tx.nb_write(1), Err(NBWriteError::Closed)));
+    }
+
+    #[tokio::test]
+    async fn test_fifo_delta_cycles() {
+        let mut sim = Simulator::with_scheduler(SchedulerMode::Deterministic { seed: None });
+        let (tx, mut rx) = fifo(1);
+        sim.spawn(async move {
+            assert!(tx.nb_write(1).is_ok());
+            assert_eq!(0, tx.num_free());
+            // The written value is only readable at the next delta-cycle.
+            assert_eq!(0, rx.num_available());
+            crate::wait().await.unwrap_or(());
+            assert_eq!(1, rx.num_available());
+            assert_eq!(1, rx.nb_read().unwrap_or(0));
+            // The room freed by a read is only available at the next delta-cycle.
+            assert_eq!(0, tx.num_free());
+            crate::wait().await.unwrap_or(());
+            assert_eq!(1, tx.num_free());
+        });
+        sim.run().await;
+    }
+
+    #[tokio::test]
+    async fn test_fifo_blocking() {
+        let mut sim = Simulator::with_scheduler(SchedulerMode::Deterministic { seed: None });
+        let (tx, mut rx) = fifo(2);
+        let received = Arc::new(AtomicUsize::new(0));
+        let count = received.clone();
+        sim.spawn(async move {
+            for i in 0..6 {
+                tx.b_write(i).await.unwrap_or(());
+            }
+            // The last writes wait for the slow reader.
+            assert_eq!(30, now().as_nanos());
+        });
+        sim.spawn(async move {
+            let mut expected = 0;
+            while let Ok(val) = rx.b_read().await {
+                assert_eq!(expected, val);
+                expected += 1;
+                count.fetch_add(1, Ordering::SeqCst);
+                wait_for(10.ns()).await;
+            }
+        });
+        sim.run().await;
+        assert_eq!(6, received.load(Ordering::SeqCst));
+    }
+}
@@ -1,3 +1,6 @@
 pub mod buffer;
+pub mod clock;
+pub mod event;
 pub mod fifo;
+pub mod resolved;
 pub mod signal;
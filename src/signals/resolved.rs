@@ -0,0 +1,315 @@
+//! This module holds the resolved signal, which can be driven by several processes.
+//!
+//! Every [Driver] holds its own contribution to the signal. At the end of each evaluation phase
+//! where a contribution changed, the contributions are resolved into the value of the signal. When
+//! two drivers drive conflicting strong values, the simulation stops with a [`ShortCircuit`].
+
+use crate::error::{NBWriteError, ShortCircuit};
+use crate::kernel::{context, Update};
+use crate::logic::{LogicVector, StdLogic};
+use crate::signal::{self, signal, Receiver, Sender};
+use crate::trace::Probe;
+use crate::{SimTime, Write};
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// A value type with a resolution function.
+pub trait Resolved: Clone + Send + PartialEq + 'static {
+    /// Contribution of a driver that does not drive the signal.
+    fn released() -> Self;
+
+    /// Resolve the contributions of every driver, or return the indices of two conflicting
+    /// drivers.
+    fn resolve_drivers(contributions: &[Self]) -> Result<Self, (usize, usize)>;
+}
+
+impl Resolved for StdLogic {
+    fn released() -> Self {
+        StdLogic::Z
+    }
+
+    fn resolve_drivers(contributions: &[Self]) -> Result<Self, (usize, usize)> {
+        for (i, a) in contributions.iter().enumerate() {
+            if let Some(j) = contributions[i + 1..]
+                .iter()
+                .position(|b| a.is_strong() && b.is_strong() && a != b)
+            {
+                return Err((i, i + 1 + j));
+            }
+        }
+        Ok(StdLogic::resolve_all(contributions.iter().copied()))
+    }
+}
+
+impl<const N: usize> Resolved for LogicVector<N> {
+    fn released() -> Self {
+        LogicVector::filled(StdLogic::Z)
+    }
+
+    fn resolve_drivers(contributions: &[Self]) -> Result<Self, (usize, usize)> {
+        let mut resolved = Self::released();
+        for bit in 0..N {
+            let bits: Vec<StdLogic> = contributions.iter().map(|c| c[bit]).collect();
+            resolved[bit] = StdLogic::resolve_drivers(&bits)?;
+        }
+        Ok(resolved)
+    }
+}
+
+type Resolution<T> = Box<dyn Fn(&[T]) -> Result<T, (usize, usize)> + Send + Sync>;
+
+struct State<T> {
+    released: T,
+    names: Vec<String>,
+    contributions: Vec<T>,
+    update_requested: bool,
+}
+
+struct Inner<T: Clone + Send> {
+    state: Mutex<State<T>>,
+    tx: Sender<T>,
+    resolution: Resolution<T>,
+}
+
+impl<T: Clone + Send + 'static> Inner<T> {
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().expect("Resolved signal state poisoned.")
+    }
+
+    /// Resolve the contributions and propagate the value.
+    ///
+    /// # Panics
+    /// Panics with a [`ShortCircuit`] if two drivers are in conflict.
+    fn resolve(&self, time: SimTime) {
+        let state = self.lock();
+        match (self.resolution)(&state.contributions) {
+            Ok(val) => {
+                drop(state);
+                self.tx.send_now(val);
+            }
+            Err((a, b)) => {
+                let short_circuit = ShortCircuit {
+                    drivers: (state.names[a].clone(), state.names[b].clone()),
+                    time,
+                };
+                drop(state);
+                panic!("{}", short_circuit);
+            }
+        }
+    }
+}
+
+/// Resolves the contributions of a *delta-cycle*.
+struct ResolveUpdate<T: Clone + Send> {
+    inner: Arc<Inner<T>>,
+    kernel: context::Handle,
+}
+
+impl<T: Clone + Send + 'static> Update for ResolveUpdate<T> {
+    fn update(self: Box<Self>) {
+        self.inner.lock().update_requested = false;
+        self.inner.resolve(self.kernel.shared.now());
+    }
+}
+
+/// A resolved signal, from which drivers and receivers are created.
+pub struct Bus<T: Clone + Send> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T: Clone + Send + 'static> Bus<T> {
+    /// Create a new named driver of the signal, which starts released.
+    pub fn driver(&self, name: &str) -> Driver<T> {
+        let mut state = self.inner.lock();
+        state.names.push(name.to_string());
+        let released = state.released.clone();
+        state.contributions.push(released);
+        Driver {
+            inner: self.inner.clone(),
+            index: state.contributions.len() - 1,
+        }
+    }
+
+    /// Create a new Receiver of the resolved value.
+    pub fn subscribe(&self) -> Receiver<T> {
+        self.inner.tx.subscribe()
+    }
+
+    /// Returns the hierarchical path of the signal, if it was constructed with [`named`].
+    pub fn name(&self) -> Option<&str> {
+        self.inner.tx.name()
+    }
+}
+
+impl<T: Clone + Send + 'static> Probe<T> for Bus<T> {
+    fn probe(&self) -> Receiver<T> {
+        self.subscribe()
+    }
+}
+
+/// One of the drivers of a resolved signal.
+pub struct Driver<T: Clone + Send> {
+    inner: Arc<Inner<T>>,
+    index: usize,
+}
+
+impl<T: Clone + Send + 'static> Driver<T> {
+    /// Stop driving the signal.
+    pub fn release(&self) {
+        let released = self.inner.lock().released.clone();
+        let _ = self.nb_write(released);
+    }
+}
+
+#[async_trait]
+impl<T: Clone + Send + 'static> Write<T> for Driver<T> {
+    /// Set the contribution of this driver.
+    ///
+    /// The signal is resolved at the end of the evaluation phase. The resolved signal holds its
+    /// own Receiver, so this never fails.
+    fn nb_write(&self, val: T) -> Result<(), NBWriteError> {
+        let mut state = self.inner.lock();
+        state.contributions[self.index] = val;
+        match context::current() {
+            Some(kernel) => {
+                if !state.update_requested {
+                    state.update_requested = true;
+                    kernel.shared.request_update(Box::new(ResolveUpdate {
+                        inner: self.inner.clone(),
+                        kernel: kernel.clone(),
+                    }));
+                }
+            }
+            None => {
+                // Outside of a simulation there are no delta-cycles, so the signal resolves now.
+                drop(state);
+                self.inner.resolve(SimTime::ZERO);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Constructs a resolved signal using the resolution function of its value type and returns the
+/// Bus and Receiver handles.
+pub fn resolved<T: Resolved>() -> (Bus<T>, Receiver<T>) {
+    resolved_with(T::released(), T::resolve_drivers)
+}
+
+/// Constructs a resolved signal with a hierarchical path, such as `"top.bus.data"`, and returns
+/// the Bus and Receiver handles.
+pub fn named<T: Resolved>(path: &str) -> (Bus<T>, Receiver<T>) {
+    new_resolved(signal::named(path), T::released(), T::resolve_drivers)
+}
+
+/// Constructs a resolved signal with a user resolution function and returns the Bus and Receiver
+/// handles.
+///
+/// The resolution function returns the resolved value, or the indices of two conflicting drivers.
+/// `released` is the contribution of a driver that does not drive the signal.
+pub fn resolved_with<T, F>(released: T, resolution: F) -> (Bus<T>, Receiver<T>)
+where
+    T: Clone + Send + 'static,
+    F: Fn(&[T]) -> Result<T, (usize, usize)> + Send + Sync + 'static,
+{
+    new_resolved(signal(), released, resolution)
+}
+
+fn new_resolved<T, F>(
+    channel: (Sender<T>, Receiver<T>),
+    released: T,
+    resolution: F,
+) -> (Bus<T>, Receiver<T>)
+where
+    T: Clone + Send + 'static,
+    F: Fn(&[T]) -> Result<T, (usize, usize)> + Send + Sync + 'static,
+{
+    let (tx, rx) = channel;
+    let inner = Arc::new(Inner {
+        state: Mutex::new(State {
+            released,
+            names: Vec::new(),
+            contributions: Vec::new(),
+            update_requested: false,
+        }),
+        tx,
+        resolution: Box::new(resolution),
+    });
+    (Bus { inner }, rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{wait_for, Read, SchedulerMode, Simulator, TimeExt};
+
+    #[test]
+    fn test_std_logic_resolution() {
+        use StdLogic::{One, Zero, H, Z};
+        assert_eq!(Ok(One), StdLogic::resolve_drivers(&[Z, H, One]));
+        assert_eq!(Err((1, 3)), StdLogic::resolve_drivers(&[Z, Zero, H, One]));
+        assert_eq!(Ok(Z), StdLogic::resolve_drivers(&[]));
+    }
+
+    #[test]
+    fn test_vector_resolution() {
+        let a: LogicVector<4> = "ZZ10".parse().unwrap();
+        let b: LogicVector<4> = "01ZZ".parse().unwrap();
+        assert_eq!(
+            Ok("0110".parse().unwrap()),
+            LogicVector::resolve_drivers(&[a, b])
+        );
+        assert_eq!(Err((0, 1)), LogicVector::resolve_drivers(&[a, !a]));
+    }
+
+    #[tokio::test]
+    async fn test_resolved_drivers() {
+        let mut sim = Simulator::with_scheduler(SchedulerMode::Deterministic { seed: None });
+        let (bus, mut rx) = resolved::<StdLogic>();
+        let (a, b) = (bus.driver("a"), bus.driver("b"));
+        sim.spawn(async move {
+            a.nb_write(StdLogic::H).unwrap();
+            wait_for(10.ns()).await;
+            a.release();
+        });
+        sim.spawn(async move {
+            wait_for(5.ns()).await;
+            b.nb_write(StdLogic::Zero).unwrap();
+        });
+        sim.spawn(async move {
+            assert_eq!(StdLogic::H, rx.b_read().await.unwrap_or(StdLogic::U));
+            // The strong driver wins over the weak one.
+            assert_eq!(StdLogic::Zero, rx.b_read().await.unwrap_or(StdLogic::U));
+        });
+        sim.run().await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Short circuit between drivers `cpu` and `dma` at 5 ns.")]
+    async fn test_short_circuit() {
+        let mut sim = Simulator::with_scheduler(SchedulerMode::Deterministic { seed: None });
+        let (bus, _rx) = resolved::<StdLogic>();
+        let (cpu, dma) = (bus.driver("cpu"), bus.driver("dma"));
+        sim.spawn(async move {
+            cpu.nb_write(StdLogic::One).unwrap();
+            wait_for(10.ns()).await;
+        });
+        sim.spawn(async move {
+            wait_for(5.ns()).await;
+            dma.nb_write(StdLogic::Zero).unwrap();
+        });
+        sim.run().await;
+    }
+
+    #[test]
+    fn test_user_resolution() {
+        // Wired-or of every driver, which never conflicts.
+        let (bus, mut rx) = resolved_with(0u8, |values: &[u8]| {
+            Ok(values.iter().fold(0, |acc, v| acc | v))
+        });
+        let (a, b) = (bus.driver("a"), bus.driver("b"));
+        a.nb_write(0x0f).unwrap();
+        b.nb_write(0x30).unwrap();
+        assert_eq!(0x3f, rx.nb_read().unwrap_or(0));
+    }
+}
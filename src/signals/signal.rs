@@ -1,34 +1,99 @@
 //! This module holds the basic signal.
 
-use crate::error::{BReadError, NBReadError};
+use crate::error::{BReadError, NBReadError, NBWriteError};
+use crate::event::Event;
+use crate::kernel::{context, Update};
+use crate::trace::Probe;
 use crate::{Read, Write};
 use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
 use tokio::sync::broadcast;
 use tokio::sync::broadcast::channel;
 use tokio::sync::broadcast::error::{RecvError, TryRecvError};
 
 /// Wrapper for the `tokio::sync::braodcast::Sender`.
+///
+/// Inside a simulation, the written value is held until the update phase of the kernel.
 #[derive(Clone)]
 pub struct Sender<T> {
     tx: broadcast::Sender<T>,
+    pending: Arc<Mutex<Option<T>>>,
+    name: Option<Arc<str>>,
 }
 
 impl<T: Clone + Send> Sender<T> {
     /// Create a new Receiver connected to this Sender.
-    pub(crate) fn subscribe(&self) -> Receiver<T> {
+    pub fn subscribe(&self) -> Receiver<T> {
         Receiver {
             rx: self.tx.subscribe(),
             value: None,
+            name: self.name.clone(),
         }
     }
+
+    /// Returns the hierarchical path of the signal, if it was constructed with [`named`].
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Propagate a value immediately, bypassing the update phase of the kernel.
+    pub(crate) fn send_now(&self, val: T) {
+        let _ = self.tx.send(val);
+    }
 }
 
 #[async_trait]
-impl<T: Clone + Send> Write<T> for Sender<T> {
-    fn nb_write(&self, val: T) {
-        match self.tx.send(val) {
-            Ok(_) => {}
-            Err(_) => panic!("Unable to send on signal channel."),
+impl<T: Clone + Send + 'static> Write<T> for Sender<T> {
+    /// Returns [`NBWriteError::Closed`] if every Receiver of the signal is dropped.
+    fn nb_write(&self, val: T) -> Result<(), NBWriteError> {
+        if self.tx.receiver_count() == 0 {
+            return Err(NBWriteError::Closed);
+        }
+        match context::current() {
+            Some(kernel) => {
+                let requested = self
+                    .pending
+                    .lock()
+                    .expect("Signal pending value poisoned.")
+                    .replace(val)
+                    .is_some();
+                if !requested {
+                    kernel.shared.request_update(Box::new(PendingWrite {
+                        tx: self.tx.clone(),
+                        pending: self.pending.clone(),
+                    }));
+                }
+            }
+            None => {
+                // Outside of a simulation there are no delta-cycles, so the value propagates now.
+                let _ = self.tx.send(val);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: Clone + Send> Probe<T> for Sender<T> {
+    fn probe(&self) -> Receiver<T> {
+        self.subscribe()
+    }
+}
+
+/// Value written to a signal during the current evaluation phase.
+struct PendingWrite<T> {
+    tx: broadcast::Sender<T>,
+    pending: Arc<Mutex<Option<T>>>,
+}
+
+impl<T: Send> Update for PendingWrite<T> {
+    fn update(self: Box<Self>) {
+        let val = self
+            .pending
+            .lock()
+            .expect("Signal pending value poisoned.")
+            .take();
+        if let Some(val) = val {
+            let _ = self.tx.send(val);
         }
     }
 }
@@ -39,6 +104,49 @@ impl<T: Clone + Send> Write<T> for Sender<T> {
 pub struct Receiver<T: Clone + Send> {
     rx: broadcast::Receiver<T>,
     value: Option<T>,
+    name: Option<Arc<str>>,
+}
+
+impl<T: Clone + Send> Receiver<T> {
+    /// Returns the hierarchical path of the signal, if it was constructed with [`named`].
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Read the next value written to the signal, even if it is the same as the previous one.
+    pub(crate) async fn recv_write(&mut self) -> Result<T, BReadError> {
+        let _waiting = context::wait_on(self.name());
+        loop {
+            match self.rx.recv().await {
+                Ok(val) => {
+                    self.value = Some(val.clone());
+                    return Ok(val);
+                }
+                Err(err) => match err {
+                    RecvError::Closed => return Err(BReadError::Closed),
+                    RecvError::Lagged(skipped) => self.lagged(skipped),
+                },
+            }
+        }
+    }
+
+    /// Report the values missed by this Receiver to the kernel.
+    fn lagged(&self, skipped: u64) {
+        if let Some(kernel) = context::current() {
+            kernel.shared.report_lag(self.name(), skipped);
+        }
+    }
+}
+
+impl<T: Clone + Send> Probe<T> for Receiver<T> {
+    /// The new Receiver starts from the last value received by this one.
+    fn probe(&self) -> Receiver<T> {
+        Receiver {
+            rx: self.rx.resubscribe(),
+            value: self.value.clone(),
+            name: self.name.clone(),
+        }
+    }
 }
 
 #[async_trait]
@@ -55,12 +163,16 @@ impl<T: Clone + Send + PartialEq> Read<T> for Receiver<T> {
                     Some(val) => Ok(val),
                 },
                 TryRecvError::Closed => Err(NBReadError::Closed),
-                TryRecvError::Lagged(_) => self.nb_read(),
+                TryRecvError::Lagged(skipped) => {
+                    self.lagged(skipped);
+                    self.nb_read()
+                }
             },
         }
     }
 
     async fn b_read(&mut self) -> Result<T, BReadError> {
+        let _waiting = context::wait_on(self.name());
         loop {
             match self.rx.recv().await {
                 Ok(val) => {
@@ -76,44 +188,61 @@ impl<T: Clone + Send + PartialEq> Read<T> for Receiver<T> {
                 }
                 Err(err) => match err {
                     RecvError::Closed => return Err(BReadError::Closed),
-                    RecvError::Lagged(_) => {}
+                    RecvError::Lagged(skipped) => self.lagged(skipped),
                 },
             }
         }
     }
 
     async fn event(&mut self) {
-        self.b_read().await;
+        let _ = self.b_read().await;
+    }
+}
+
+impl<T: Clone + Send + PartialEq + 'static> Receiver<T> {
+    /// Returns the change event of the signal, for the sensitivity list of a method process.
+    pub fn changed(&self) -> Event {
+        Event::changed(self.probe())
     }
 }
 
 impl Receiver<bool> {
     /// Suspend the process until a positive edge event is detected on the signal.
     ///
+    /// An edge is a change from a known value, so the first value received is never an edge.
+    ///
     /// *This is only implemented for boolean value types.*
-    async fn posedge_event(&mut self) {
-        loop {
-            if let Ok(val) = self.b_read().await {
-                if val {
-                    return;
-                }
-            } else {
-                return;
-            }
-        }
+    pub async fn posedge_event(&mut self) {
+        let _ = self.edge(true).await;
     }
 
     /// Suspend the process until a negative edge event is detected on the signal.
     ///
+    /// An edge is a change from a known value, so the first value received is never an edge.
+    ///
     /// *This is only implemented for boolean value types.*
-    async fn negedge_event(&mut self) {
+    pub async fn negedge_event(&mut self) {
+        let _ = self.edge(false).await;
+    }
+
+    /// Returns the positive edge event of the signal, for the sensitivity list of a method
+    /// process.
+    pub fn pos(&self) -> Event {
+        Event::edge(self.probe(), true)
+    }
+
+    /// Returns the negative edge event of the signal, for the sensitivity list of a method
+    /// process.
+    pub fn neg(&self) -> Event {
+        Event::edge(self.probe(), false)
+    }
+
+    /// Read the signal until it changes from the opposite of `level` to `level`.
+    pub(crate) async fn edge(&mut self, level: bool) -> Result<(), BReadError> {
         loop {
-            if let Ok(val) = self.b_read().await {
-                if !val {
-                    return;
-                }
-            } else {
-                return;
+            let previous = self.value;
+            if self.b_read().await? == level && previous == Some(!level) {
+                return Ok(());
             }
         }
     }
@@ -121,8 +250,32 @@ impl Receiver<bool> {
 
 /// Contructs a signal and returns the Sender and Receiver handles.
 pub fn signal<T: Clone + Send>() -> (Sender<T>, Receiver<T>) {
+    new_signal(None)
+}
+
+/// Contructs a signal with a hierarchical path, such as `"top.cpu.clk"`, and returns the Sender
+/// and Receiver handles.
+///
+/// The path names the signal in the error messages, it is registered in the design hierarchy
+/// with [`Simulator::register`](crate::Simulator::register).
+pub fn named<T: Clone + Send>(path: &str) -> (Sender<T>, Receiver<T>) {
+    new_signal(Some(path.into()))
+}
+
+fn new_signal<T: Clone + Send>(name: Option<Arc<str>>) -> (Sender<T>, Receiver<T>) {
     let (tx, rx) = channel(1);
-    (Sender { tx }, Receiver { rx, value: None })
+    (
+        Sender {
+            tx,
+            pending: Arc::new(Mutex::new(None)),
+            name: name.clone(),
+        },
+        Receiver {
+            rx,
+            value: None,
+            name,
+        },
+    )
 }
 
 #[cfg(test)]
@@ -134,11 +287,20 @@ mod tests {
         let _: (Sender<i32>, Receiver<i32>) = signal();
     }
 
+    #[test]
+    fn test_signal_named() {
+        let (tx, rx) = named::<i32>("top.cpu.clk");
+        assert_eq!(Some("top.cpu.clk"), tx.name());
+        assert_eq!(Some("top.cpu.clk"), tx.subscribe().name());
+        assert_eq!(Some("top.cpu.clk"), rx.probe().name());
+        assert_eq!(None, signal::<i32>().0.name());
+    }
+
     #[test]
     fn test_signal_nb_read() {
         static TEST_VALUE: i32 = 42;
         let (tx, mut rx) = signal();
-        tx.tx.send(TEST_VALUE);
+        tx.tx.send(TEST_VALUE).unwrap();
         assert_eq!(TEST_VALUE, rx.nb_read().unwrap_or(0));
     }
 
@@ -146,7 +308,7 @@ mod tests {
     async fn test_signal_b_read() {
         static TEST_VALUE: i32 = 42;
         let (tx, mut rx) = signal();
-        tx.tx.send(TEST_VALUE);
+        tx.tx.send(TEST_VALUE).unwrap();
         assert_eq!(TEST_VALUE, rx.b_read().await.unwrap_or(0));
     }
 
@@ -154,42 +316,51 @@ mod tests {
     async fn test_signal_nb_write() {
         static TEST_VALUE: i32 = 42;
         let (tx, mut rx) = signal();
-        tx.nb_write(TEST_VALUE);
+        tx.nb_write(TEST_VALUE).unwrap();
         assert_eq!(TEST_VALUE, rx.rx.recv().await.unwrap_or(0));
+        drop(rx);
+        assert_eq!(Err(NBWriteError::Closed), tx.nb_write(TEST_VALUE));
     }
 
-    #[tokio::test]
+    #[crate::test]
     async fn test_signal_change_event() {
         let (tx, mut rx) = signal();
-        tokio::task::spawn(async move {
-            rx.event().await;
-            assert_eq!(41, rx.nb_read().unwrap_or(0));
-            rx.event().await;
-            assert_eq!(42, rx.nb_read().unwrap_or(0));
+        crate::spawn(async move {
+            tx.nb_write(41).unwrap();
+            crate::wait().await.unwrap();
+            tx.nb_write(42).unwrap();
+            // Closing the signal would hide the last value from `nb_read`.
+            crate::wait().await.unwrap();
         });
-        tx.nb_write(41);
-        tx.nb_write(42);
+        rx.event().await;
+        assert_eq!(41, rx.nb_read().unwrap_or(0));
+        rx.event().await;
+        assert_eq!(42, rx.nb_read().unwrap_or(0));
     }
 
-    #[tokio::test]
+    #[crate::test]
     async fn test_signal_posedge_event() {
         let (tx, mut rx) = signal();
-        tokio::task::spawn(async move {
-            rx.posedge_event().await;
-            assert!(rx.nb_read().unwrap_or(false));
+        crate::spawn(async move {
+            tx.nb_write(false).unwrap();
+            crate::wait().await.unwrap();
+            tx.nb_write(true).unwrap();
+            crate::wait().await.unwrap();
         });
-        tx.nb_write(false);
-        tx.nb_write(true);
+        rx.posedge_event().await;
+        assert!(rx.nb_read().unwrap_or(false));
     }
 
-    #[tokio::test]
+    #[crate::test]
     async fn test_signal_negedge_event() {
         let (tx, mut rx) = signal();
-        tokio::task::spawn(async move {
-            rx.negedge_event().await;
-            assert!(!rx.nb_read().unwrap_or(true));
+        crate::spawn(async move {
+            tx.nb_write(true).unwrap();
+            crate::wait().await.unwrap();
+            tx.nb_write(false).unwrap();
+            crate::wait().await.unwrap();
         });
-        tx.nb_write(true);
-        tx.nb_write(false);
+        rx.negedge_event().await;
+        assert!(!rx.nb_read().unwrap_or(true));
     }
 }
@@ -0,0 +1,80 @@
+//! This module holds the LLHD IR generated from the `#[entity]` functions.
+//!
+//! Every free function marked with `#[entity]` is lowered at compile time to an LLHD entity
+//! unit, stored next to the function in a constant named after it, e.g. `ADDER_ENTITY` for
+//! `fn adder`. The arguments of the function are the input signals of the entity, and its return
+//! value drives the `out` output signal, or `out0`, `out1`... for a tuple.
+//!
+//! The entities of a design are gathered into a single module with [`module`], which can then be
+//! written to a `.llhd` file with [`write`].
+
+pub use llhd;
+use llhd::ir::Module;
+use llhd::verifier::Verifier;
+use std::io;
+
+/// The LLHD IR of an `#[entity]` function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Entity {
+    /// Name of the entity unit, which is the name of the function.
+    pub name: &'static str,
+    /// The entity unit, in the LLHD assembly format.
+    pub assembly: &'static str,
+}
+
+/// Gather the entities in a module, then link and verify it.
+///
+/// This returns the parsing or verifier errors of the module.
+pub fn module(entities: &[Entity]) -> Result<Module, String> {
+    let assembly: String = entities.iter().map(|entity| entity.assembly).collect();
+    let mut module = llhd::assembly::parse_module_unchecked(assembly)?;
+    module.link();
+    let mut verifier = Verifier::new();
+    verifier.verify_module(&module);
+    verifier.finish().map_err(|errors| errors.to_string())?;
+    Ok(module)
+}
+
+/// Write the module in the LLHD assembly format.
+pub fn write(out: impl io::Write, module: &Module) {
+    llhd::assembly::write_module(out, module);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOT: Entity = Entity {
+        name: "not",
+        assembly: "entity @not (i1$ %a) -> (i1$ %out) {
+    %0 = prb i1$ %a
+    %1 = not i1 %0
+    %2 = const time 0s 1d
+    drv i1$ %out, %1, %2
+}
+",
+    };
+
+    #[test]
+    fn test_module() {
+        let module = module(&[NOT]).unwrap();
+        assert_eq!(1, module.entities().count());
+        let mut out = Vec::new();
+        write(&mut out, &module);
+        assert_eq!(NOT.assembly, String::from_utf8(out).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_module() {
+        let invalid = Entity {
+            name: "invalid",
+            assembly: "entity @invalid (i1$ %a) -> (i8$ %out) {
+    %0 = prb i1$ %a
+    %1 = const time 0s 1d
+    drv i8$ %out, %0, %1
+}
+",
+        };
+        assert!(module(&[NOT, invalid]).is_err());
+    }
+}
@@ -0,0 +1,116 @@
+//! Runtime of the [`#[sand::test]`](crate::test) attribute.
+//!
+//! A testbench is an `async` test function run as a process of its own [`Simulator`], with the
+//! deterministic scheduler so that the test is reproducible, on a tokio runtime of its own so that
+//! the testbench can use tokio too. The simulation stops when the
+//! testbench returns, and a panic in the testbench or in any process it spawns fails the test,
+//! with the simulated time of the failure.
+
+use crate::kernel::panic_message;
+use crate::{SchedulerMode, SimTime, Simulator};
+use futures::FutureExt;
+use std::future::Future;
+use std::panic::{resume_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Run `testbench` as the process `name` of a new simulator, until it returns or until the
+/// simulated time reaches `until`.
+///
+/// # Panics
+/// Panics if a process panics, or if the testbench did not return before the end of the
+/// simulation, with the simulated time at which it happened.
+pub fn run<F>(name: &str, until: Option<SimTime>, testbench: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Unable to start the runtime of the testbench.");
+    let mut sim = Simulator::with_scheduler(SchedulerMode::Deterministic { seed: None });
+    let finished = Arc::new(AtomicBool::new(false));
+    let done = finished.clone();
+    sim.spawn_named(name, async move {
+        testbench.await;
+        done.store(true, Ordering::SeqCst);
+        crate::stop();
+    });
+
+    let run = async {
+        match until {
+            Some(until) => sim.run_until(until).await,
+            None => sim.run().await,
+        }
+    };
+    if let Err(panic) = runtime.block_on(AssertUnwindSafe(run).catch_unwind()) {
+        resume_unwind(Box::new(format!(
+            "The testbench failed at {}: {}",
+            sim.time(),
+            panic_message(&*panic)
+        )));
+    }
+    if !finished.load(Ordering::SeqCst) {
+        panic!(
+            "The testbench `{}` did not finish before {}.",
+            name,
+            sim.time()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+    use crate::signal::signal;
+    use crate::{wait_for, Read, TimeExt, Write};
+
+    #[test]
+    fn test_run() {
+        run("testbench", None, async {
+            // The clock runs forever, but the simulation stops with the testbench.
+            let clock = Clock::new(5.ns());
+            let _clk = clock.subscribe();
+            crate::spawn(clock.process());
+            let (tx, mut rx) = signal();
+            crate::spawn(async move {
+                wait_for(10.ns()).await;
+                tx.nb_write(1).unwrap();
+            });
+            assert_eq!(Ok(1), rx.b_read().await);
+            assert_eq!(10, crate::now().as_nanos());
+            // The testbench runs on a tokio runtime.
+            let _ = tokio::runtime::Handle::current();
+        });
+    }
+
+    #[test]
+    fn test_run_failure() {
+        let panic = std::panic::catch_unwind(|| {
+            run("testbench", None, async {
+                crate::spawn(async {
+                    wait_for(10.ns()).await;
+                    panic!("Wrong value.");
+                });
+                wait_for(20.ns()).await;
+            })
+        })
+        .unwrap_err();
+        assert_eq!(
+            "The testbench failed at 10 ns: Wrong value.",
+            panic_message(&*panic)
+        );
+
+        let panic = std::panic::catch_unwind(|| {
+            run("testbench", Some(SimTime::ZERO + 5.ns()), async {
+                wait_for(10.ns()).await;
+            })
+        })
+        .unwrap_err();
+        assert_eq!(
+            "The testbench `testbench` did not finish before 5 ns.",
+            panic_message(&*panic)
+        );
+    }
+}
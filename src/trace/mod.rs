@@ -0,0 +1,181 @@
+//! This module holds the tracing of signal values to waveform files.
+//!
+//! A signal is registered with [`Simulator::trace`](crate::Simulator::trace) under a
+//! hierarchical name such as `"top.cpu.clk"`, where every dot separates a scope. The values of
+//! the registered signals are sampled at the end of every simulated time step, once every
+//! *delta-cycle* is done, and the changes are written to the waveform file given to
+//! [`Simulator::dump_vcd`](crate::Simulator::dump_vcd).
+
+mod vcd;
+
+use crate::error::NBReadError;
+use crate::signal::Receiver;
+use crate::{LogicVector, Read, StdLogic};
+pub(crate) use vcd::Vcd;
+
+/// A value type that can be traced in a waveform.
+pub trait Trace: Clone + Send + PartialEq + 'static {
+    /// Number of bits of the value.
+    const WIDTH: usize;
+
+    /// Append the `WIDTH` bits of the value to `bits`, most significant bit first, as the VCD
+    /// characters `0`, `1`, `x` and `z`.
+    fn trace_bits(&self, bits: &mut String);
+
+    /// Append the waveform variables of a value traced under `name`, as their hierarchical name
+    /// and width, in the order of their bits in [`trace_bits`](Trace::trace_bits).
+    ///
+    /// A value is traced as a single variable, unless it is made of fields such as a struct
+    /// deriving [`Signal`](crate::Signal).
+    fn trace_vars(name: &str, vars: &mut Vec<(String, usize)>) {
+        vars.push((name.to_string(), Self::WIDTH));
+    }
+}
+
+/// A handle on a signal, from which the signal can be traced.
+pub trait Probe<T: Clone + Send> {
+    /// Create a new Receiver of the signal values.
+    fn probe(&self) -> Receiver<T>;
+}
+
+impl Trace for bool {
+    const WIDTH: usize = 1;
+
+    fn trace_bits(&self, bits: &mut String) {
+        bits.push(if *self { '1' } else { '0' });
+    }
+}
+
+macro_rules! trace_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl Trace for $ty {
+                const WIDTH: usize = <$ty>::BITS as usize;
+
+                fn trace_bits(&self, bits: &mut String) {
+                    bits.push_str(&format!("{:0width$b}", self, width = Self::WIDTH));
+                }
+            }
+        )*
+    };
+}
+
+trace_integer!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl Trace for StdLogic {
+    const WIDTH: usize = 1;
+
+    /// The VCD format only has four states, so the weak values are traced as their strong
+    /// counterparts, and the uninitialized and don't care values are traced as unknown.
+    fn trace_bits(&self, bits: &mut String) {
+        bits.push(match self {
+            StdLogic::Zero | StdLogic::L => '0',
+            StdLogic::One | StdLogic::H => '1',
+            StdLogic::Z => 'z',
+            _ => 'x',
+        });
+    }
+}
+
+impl<const N: usize> Trace for LogicVector<N> {
+    const WIDTH: usize = N;
+
+    fn trace_bits(&self, bits: &mut String) {
+        self.bits()
+            .iter()
+            .rev()
+            .for_each(|bit| bit.trace_bits(bits));
+    }
+}
+
+impl<T: Trace, const N: usize> Trace for [T; N] {
+    const WIDTH: usize = N * T::WIDTH;
+
+    /// The element `0` holds the least significant bits.
+    fn trace_bits(&self, bits: &mut String) {
+        self.iter()
+            .rev()
+            .for_each(|element| element.trace_bits(bits));
+    }
+}
+
+/// A signal registered for tracing.
+pub(crate) struct Traced {
+    /// Waveform variables of the signal, as their hierarchical name and width.
+    pub(crate) vars: Vec<(String, usize)>,
+    channel: Box<dyn Sample>,
+}
+
+impl Traced {
+    pub(crate) fn new<T: Trace>(name: &str, rx: Receiver<T>) -> Self {
+        let mut vars = Vec::new();
+        T::trace_vars(name, &mut vars);
+        Traced {
+            vars,
+            channel: Box::new(Channel {
+                rx,
+                last: None,
+                sampled: false,
+            }),
+        }
+    }
+
+    /// Append the bits of the signal value if it changed since the previous sample.
+    ///
+    /// Returns false if the value did not change.
+    pub(crate) fn sample(&mut self, bits: &mut String) -> bool {
+        self.channel.sample(bits)
+    }
+}
+
+trait Sample: Send {
+    fn sample(&mut self, bits: &mut String) -> bool;
+}
+
+struct Channel<T: Clone + Send> {
+    rx: Receiver<T>,
+    last: Option<T>,
+    sampled: bool,
+}
+
+impl<T: Trace> Sample for Channel<T> {
+    fn sample(&mut self, bits: &mut String) -> bool {
+        let value = match self.rx.nb_read() {
+            Ok(value) => Some(value),
+            Err(NBReadError::Empty) => None,
+            // The signal keeps its last value once every writer is gone.
+            Err(NBReadError::Closed) => self.last.clone(),
+        };
+        if self.sampled && value == self.last {
+            return false;
+        }
+        self.sampled = true;
+        match &value {
+            Some(value) => value.trace_bits(bits),
+            None => bits.extend(std::iter::repeat_n('x', T::WIDTH)),
+        }
+        self.last = value;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bits<T: Trace>(value: T) -> String {
+        let mut bits = String::new();
+        value.trace_bits(&mut bits);
+        bits
+    }
+
+    #[test]
+    fn test_trace_bits() {
+        assert_eq!("1", bits(true));
+        assert_eq!("00000101", bits(5u8));
+        assert_eq!("11111110", bits(-2i8));
+        assert_eq!("x", bits(StdLogic::U));
+        assert_eq!("10zx", bits("HLZW".parse::<LogicVector<4>>().unwrap()));
+        assert_eq!("0000001000000001", bits([1u8, 2]));
+    }
+}
@@ -0,0 +1,322 @@
+//! This module holds the Value Change Dump writer, as specified by IEEE 1364.
+
+use super::Traced;
+use crate::SimTime;
+use std::io;
+
+/// Writer of the traced signals to a VCD file.
+pub(crate) struct Vcd {
+    out: Box<dyn io::Write + Send>,
+    signals: Vec<Traced>,
+    vars: Vec<Var>,
+    /// Time of the last timestamp written.
+    time: Option<SimTime>,
+}
+
+/// A waveform variable, which is a traced signal or one of its fields.
+struct Var {
+    id: String,
+    name: String,
+    width: usize,
+    /// Index of the traced signal holding the variable.
+    signal: usize,
+    /// Index of the first bit of the variable in the bits of the signal.
+    offset: usize,
+    /// Bits of the last value written.
+    last: Option<String>,
+}
+
+impl Vcd {
+    /// Write the header declaring the signals.
+    pub(crate) fn new(
+        mut out: Box<dyn io::Write + Send>,
+        signals: Vec<Traced>,
+    ) -> io::Result<Self> {
+        let mut vars: Vec<Var> = Vec::new();
+        for (signal, traced) in signals.iter().enumerate() {
+            let mut offset = 0;
+            for (name, width) in traced.vars.iter() {
+                vars.push(Var {
+                    id: String::new(),
+                    name: name.clone(),
+                    width: *width,
+                    signal,
+                    offset,
+                    last: None,
+                });
+                offset += width;
+            }
+        }
+        // Group the variables by scope, so that every scope is declared once.
+        vars.sort_by(|a, b| scope(&a.name).split('.').cmp(scope(&b.name).split('.')));
+        for (index, var) in vars.iter_mut().enumerate() {
+            var.id = identifier(index);
+        }
+
+        writeln!(
+            out,
+            "$version {} {} $end",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        )?;
+        writeln!(out, "$timescale 1ns $end")?;
+        let mut scopes: Vec<&str> = Vec::new();
+        for var in vars.iter() {
+            let path: Vec<&str> = var.name.split('.').collect();
+            let (name, path) = path.split_last().expect("Split returns at least one item.");
+            let common = scopes
+                .iter()
+                .zip(path.iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+            for _ in common..scopes.len() {
+                writeln!(out, "$upscope $end")?;
+            }
+            scopes.truncate(common);
+            for module in &path[common..] {
+                writeln!(out, "$scope module {} $end", module)?;
+                scopes.push(module);
+            }
+            match var.width {
+                1 => writeln!(out, "$var wire 1 {} {} $end", var.id, name)?,
+                width => writeln!(
+                    out,
+                    "$var wire {} {} {} [{}:0] $end",
+                    width,
+                    var.id,
+                    name,
+                    width - 1
+                )?,
+            }
+        }
+        for _ in scopes {
+            writeln!(out, "$upscope $end")?;
+        }
+        writeln!(out, "$enddefinitions $end")?;
+
+        Ok(Vcd {
+            out,
+            signals,
+            vars,
+            time: None,
+        })
+    }
+
+    /// Write the signal values that changed since the previous sample.
+    pub(crate) fn sample(&mut self, now: SimTime) -> io::Result<()> {
+        let first = self.time.is_none();
+        let values: Vec<Option<String>> = self
+            .signals
+            .iter_mut()
+            .map(|traced| {
+                let mut bits = String::new();
+                traced.sample(&mut bits).then_some(bits)
+            })
+            .collect();
+        let mut changes = String::new();
+        for var in self.vars.iter_mut() {
+            let bits = match &values[var.signal] {
+                Some(bits) => &bits[var.offset..var.offset + var.width],
+                None => continue,
+            };
+            if var.last.as_deref() == Some(bits) {
+                continue;
+            }
+            if var.width == 1 {
+                changes.push_str(&format!("{}{}\n", bits, var.id));
+            } else {
+                changes.push_str(&format!("b{} {}\n", bits, var.id));
+            }
+            var.last = Some(bits.to_string());
+        }
+        if changes.is_empty() && !first {
+            return Ok(());
+        }
+        writeln!(self.out, "#{}", now.as_nanos())?;
+        if first {
+            write!(self.out, "$dumpvars\n{}$end\n", changes)?;
+        } else {
+            write!(self.out, "{}", changes)?;
+        }
+        self.time = Some(now);
+        Ok(())
+    }
+
+    /// Write the end time of the simulation, so that the viewers show the last values until then.
+    pub(crate) fn finish(&mut self, now: SimTime) -> io::Result<()> {
+        if self.time.is_none_or(|time| time < now) {
+            writeln!(self.out, "#{}", now.as_nanos())?;
+            self.time = Some(now);
+        }
+        self.out.flush()
+    }
+}
+
+/// Returns the scope of a hierarchical name, without the last segment.
+fn scope(name: &str) -> &str {
+    name.rsplit_once('.').map_or("", |(scope, _)| scope)
+}
+
+/// Returns the short identifier code of a signal, using the printable ASCII characters.
+fn identifier(mut index: usize) -> String {
+    const FIRST: u8 = b'!';
+    const COUNT: usize = (b'~' - b'!' + 1) as usize;
+    let mut id = String::new();
+    loop {
+        id.push((FIRST + (index % COUNT) as u8) as char);
+        index /= COUNT;
+        if index == 0 {
+            return id;
+        }
+        index -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::NBWriteError;
+    use crate::signal::signal;
+    use crate::{wait_for, LogicVector, SchedulerMode, Simulator, TimeExt, Write};
+    use std::sync::{Arc, Mutex};
+
+    /// Output shared with the test once the simulator is done with it.
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Output {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dump_vcd() {
+        let mut sim = Simulator::with_scheduler(SchedulerMode::Deterministic { seed: None });
+        let (clk, _clk_rx) = signal::<bool>();
+        let (data, _data_rx) = signal::<LogicVector<4>>();
+        let (count, _count_rx) = signal::<u8>();
+        sim.trace("top.clk", &clk);
+        sim.trace("top.bus.data", &data);
+        sim.trace("count", &count);
+        sim.spawn(async move {
+            clk.nb_write(false).unwrap();
+            data.nb_write("01ZX".parse().unwrap()).unwrap();
+            wait_for(10.ns()).await;
+            clk.nb_write(true).unwrap();
+            count.nb_write(3).unwrap();
+            wait_for(5.ns()).await;
+            // Writing the same value is not a change.
+            clk.nb_write(true).unwrap();
+            wait_for(5.ns()).await;
+        });
+        let output = Output::default();
+        sim.dump_vcd(output.clone());
+        sim.run().await;
+
+        let vcd = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let body = vcd.split_once("$timescale").unwrap().1;
+        assert_eq!(
+            " 1ns $end
+$var wire 8 ! count [7:0] $end
+$scope module top $end
+$var wire 1 \" clk $end
+$scope module bus $end
+$var wire 4 # data [3:0] $end
+$upscope $end
+$upscope $end
+$enddefinitions $end
+#0
+$dumpvars
+bxxxxxxxx !
+0\"
+b01zx #
+$end
+#10
+b00000011 !
+1\"
+#20
+",
+            body
+        );
+    }
+
+    #[tokio::test]
+    async fn test_trace_without_vcd() {
+        let mut sim = Simulator::with_scheduler(SchedulerMode::Deterministic { seed: None });
+        let (count, count_rx) = signal::<u8>();
+        sim.trace("top.count", &count);
+        drop(count_rx);
+        sim.spawn(async move {
+            // Without a waveform, the traced signal has no reader left.
+            assert_eq!(Err(NBWriteError::Closed), count.nb_write(1));
+        });
+        sim.run().await;
+    }
+
+    #[derive(Clone, PartialEq, crate::Signal)]
+    struct Header {
+        valid: bool,
+        address: u8,
+    }
+
+    #[tokio::test]
+    async fn test_dump_vcd_struct() {
+        let mut sim = Simulator::with_scheduler(SchedulerMode::Deterministic { seed: None });
+        let (header, _header_rx) = signal::<Header>();
+        sim.trace("top.header", &header);
+        sim.spawn(async move {
+            header
+                .nb_write(Header {
+                    valid: false,
+                    address: 1,
+                })
+                .unwrap();
+            wait_for(10.ns()).await;
+            // Only the changed field is written.
+            header
+                .nb_write(Header {
+                    valid: true,
+                    address: 1,
+                })
+                .unwrap();
+        });
+        let output = Output::default();
+        sim.dump_vcd(output.clone());
+        sim.run().await;
+
+        let vcd = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let body = vcd.split_once("$timescale").unwrap().1;
+        assert_eq!(
+            " 1ns $end
+$scope module top $end
+$scope module header $end
+$var wire 1 ! valid $end
+$var wire 8 \" address [7:0] $end
+$upscope $end
+$upscope $end
+$enddefinitions $end
+#0
+$dumpvars
+0!
+b00000001 \"
+$end
+#10
+1!
+",
+            body
+        );
+    }
+
+    #[test]
+    fn test_identifier() {
+        assert_eq!("!", identifier(0));
+        assert_eq!("~", identifier(93));
+        assert_eq!("!!", identifier(94));
+        assert_eq!("\"!", identifier(95));
+    }
+}